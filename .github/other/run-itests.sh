@@ -0,0 +1,48 @@
+#!/bin/bash
+# Copyright (c) godot-rust; Bromeon and contributors.
+# This Source Code Form is subject to the terms of the Mozilla Public
+# License, v. 2.0. If a copy of the MPL was not distributed with this
+# file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+# Runs the gameplay tests of an example headless, if it has any (scene res://itest.tscn, see common/src/itest.rs).
+# Input: $exampleCrate
+
+set -euo pipefail
+
+# Upper bound for the whole test run; tests themselves use fixed frame steps and don't depend on wall-clock time.
+TEST_TIMEOUT=120
+EDITOR_TIMEOUT=60
+
+example="$1"
+if [ -z "$example" ]; then
+    echo "::error::required argument 'example' missing."
+    exit 1
+fi
+
+PRE="# TESTS $example |"
+dir="./$example/godot"
+
+if [ ! -f "$dir/itest.tscn" ]; then
+    echo "$PRE No gameplay tests, skipping."
+    exit 0
+fi
+
+# In .gdextension file, use paths to release artifacts.
+sed -i'.bak' "s!/debug/!/release/!g" "$dir/rust.gdextension"
+
+# Resources must be imported before the scenes can be loaded.
+echo "$PRE Briefly open Godot editor, to import resources..."
+timeout "$EDITOR_TIMEOUT"s "$GODOT4_BIN" -e --headless --path "$dir" --quit || {
+    echo "::error::$PRE Godot editor failed to open in time."
+    exit 1
+}
+
+# --fixed-fps: every frame advances the game by 1/60 s, regardless of how fast the machine is.
+echo "$PRE Run gameplay tests..."
+timeout "$TEST_TIMEOUT"s "$GODOT4_BIN" --headless --fixed-fps 60 --path "$dir" res://itest.tscn || {
+    echo "::error::$PRE Gameplay tests failed (exit code $?)."
+    exit 1
+}
+
+echo "$PRE Gameplay tests passed."
+echo ""
@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+
+use crate::achievements::Achievement;
+use crate::locale::{tr, tr_format};
+
+use godot::classes::control::MouseFilter;
+use godot::classes::node::ProcessMode;
+use godot::classes::{IPanelContainer, Label, PanelContainer, VBoxContainer};
+use godot::global::HorizontalAlignment;
+use godot::prelude::*;
+
+// Seconds each toast stays on screen, including the fades.
+const DISPLAY_TIME: f64 = 3.0;
+const FADE_TIME: f64 = 0.3;
+
+/// Notification that pops up when an achievement is unlocked.
+///
+/// Like [`SettingsPanel`](crate::settings_panel::SettingsPanel), the toast builds its own content; games only place it in their UI.
+/// Achievements unlocked in quick succession are shown one after another.
+#[derive(GodotClass)]
+#[class(base=PanelContainer)]
+pub struct AchievementToast {
+    // Title and description of achievements still waiting to be shown.
+    queue: VecDeque<(String, String)>,
+    // Seconds the current toast has been shown; only meaningful while visible.
+    shown_for: f64,
+    title_label: OnReady<Gd<Label>>,
+    description_label: OnReady<Gd<Label>>,
+    base: Base<PanelContainer>,
+}
+
+#[godot_api]
+impl IPanelContainer for AchievementToast {
+    fn init(base: Base<PanelContainer>) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            shown_for: 0.0,
+            // Created in ready() together with the rest of the content.
+            title_label: OnReady::manual(),
+            description_label: OnReady::manual(),
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        // Unlocks may happen right before the game pauses, e.g. on game over; the toast should still finish.
+        self.base_mut().set_process_mode(ProcessMode::ALWAYS);
+        self.base_mut().set_mouse_filter(MouseFilter::IGNORE);
+        self.base_mut().hide();
+
+        let mut title = Label::new_alloc();
+        title.set_horizontal_alignment(HorizontalAlignment::CENTER);
+        title.add_theme_font_size_override("font_size", 22);
+
+        let mut description = Label::new_alloc();
+        description.set_horizontal_alignment(HorizontalAlignment::CENTER);
+        description.add_theme_font_size_override("font_size", 16);
+
+        let mut content = VBoxContainer::new_alloc();
+        content.set_mouse_filter(MouseFilter::IGNORE);
+        content.add_child(&title);
+        content.add_child(&description);
+        self.base_mut().add_child(&content);
+
+        self.title_label.init(title);
+        self.description_label.init(description);
+    }
+
+    fn process(&mut self, delta: f64) {
+        if !self.base().is_visible() {
+            return;
+        }
+
+        self.shown_for += delta;
+        if self.shown_for >= DISPLAY_TIME {
+            self.base_mut().hide();
+            self.show_next();
+            return;
+        }
+
+        let fade_in = self.shown_for / FADE_TIME;
+        let fade_out = (DISPLAY_TIME - self.shown_for) / FADE_TIME;
+
+        let mut modulate = self.base().get_modulate();
+        modulate.a = fade_in.min(fade_out).min(1.0) as f32;
+        self.base_mut().set_modulate(modulate);
+    }
+}
+
+#[godot_api]
+impl AchievementToast {
+    /// Shows `achievement`, once the toasts before it are done.
+    pub fn push(&mut self, achievement: &Achievement) {
+        // Translated right away; a toast is gone long before anyone could switch the language.
+        let title = tr(achievement.title);
+        self.queue.push_back((
+            tr_format("ACHIEVEMENT_UNLOCKED", &[("title", &title)]),
+            tr(achievement.description),
+        ));
+
+        if !self.base().is_visible() {
+            self.show_next();
+        }
+    }
+
+    fn show_next(&mut self) {
+        let Some((title, description)) = self.queue.pop_front() else {
+            return;
+        };
+
+        self.title_label.set_text(title.as_str());
+        self.description_label.set_text(description.as_str());
+
+        self.shown_for = 0.0;
+        self.base_mut().set_modulate(Color::TRANSPARENT_WHITE);
+        self.base_mut().show();
+    }
+}
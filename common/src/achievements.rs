@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use godot::classes::{ConfigFile, FileAccess, Time};
+use godot::global::Error;
+use godot::prelude::*;
+
+// Bump this whenever the layout of the save file changes. Files with a different version are discarded.
+const SAVE_VERSION: i64 = 1;
+
+/// A goal the player can reach once, across all runs. Each game declares its own achievements as constants.
+#[derive(Debug)]
+pub struct Achievement {
+    /// Key in the save file; must never change once released.
+    pub id: &'static str,
+    /// Translation key of the name.
+    pub title: &'static str,
+    /// Translation key of what has to be done.
+    pub description: &'static str,
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Achievements unlocked so far, persisted to `user://` between sessions.
+///
+/// The registry does not watch the game itself: games check their goals when gameplay signals arrive and call
+/// [`unlock()`](Self::unlock), which saves right away, so that unlocks survive crashes and quitting mid-run.
+pub struct Achievements {
+    /// `None` for achievements that are only kept in memory.
+    save_path: Option<String>,
+    /// Date of unlocking (`YYYY-MM-DD`), by achievement ID.
+    unlocked: BTreeMap<String, GString>,
+}
+
+impl Achievements {
+    /// Loads the achievements stored in `user://<file_name>`. A missing or outdated file results in none being unlocked.
+    pub fn load(file_name: &str) -> Self {
+        let path = format!("user://{file_name}");
+        let mut achievements = Self {
+            save_path: Some(path.clone()),
+            unlocked: BTreeMap::new(),
+        };
+
+        let path = path.as_str();
+        if !FileAccess::file_exists(path) {
+            return achievements;
+        }
+
+        let mut config = ConfigFile::new_gd();
+        if config.load(path) != Error::OK {
+            godot_warn!("Could not read achievements from {path}, starting over.");
+            return achievements;
+        }
+
+        let version = config.get_value("meta", "version").try_to::<i64>().ok();
+        if version != Some(SAVE_VERSION) {
+            godot_warn!("Achievement file {path} has an unsupported version, discarding it.");
+            return achievements;
+        }
+
+        if config.has_section("unlocked") {
+            for id in config.get_section_keys("unlocked").as_slice() {
+                if let Ok(date) = config.get_value("unlocked", id).try_to::<GString>() {
+                    achievements.unlocked.insert(id.to_string(), date);
+                }
+            }
+        }
+
+        achievements
+    }
+
+    /// Achievements that start out locked and are never saved, e.g. for tests that must not touch the player's progress.
+    pub fn in_memory() -> Self {
+        Self {
+            save_path: None,
+            unlocked: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_unlocked(&self, achievement: &Achievement) -> bool {
+        self.unlocked.contains_key(achievement.id)
+    }
+
+    /// Unlocks `achievement` and saves (unless in memory), unless it was unlocked before.
+    ///
+    /// Returns whether it was newly unlocked, i.e. whether the game should celebrate it.
+    pub fn unlock(&mut self, achievement: &Achievement) -> bool {
+        if self.is_unlocked(achievement) {
+            return false;
+        }
+
+        let date = Time::singleton().get_date_string_from_system();
+        self.unlocked.insert(achievement.id.to_string(), date);
+        self.save();
+
+        true
+    }
+
+    fn save(&self) {
+        let Some(save_path) = &self.save_path else {
+            return;
+        };
+
+        let mut config = ConfigFile::new_gd();
+        config.set_value("meta", "version", &SAVE_VERSION.to_variant());
+
+        for (id, date) in &self.unlocked {
+            config.set_value("unlocked", id.as_str(), &date.to_variant());
+        }
+
+        if config.save(save_path.as_str()) != Error::OK {
+            godot_warn!("Could not write achievements to {save_path}.");
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Gameplay tests that run inside the engine, against the real scenes of a demo.
+//!
+//! Each demo crate registers a small runner class which calls [`run_tests()`] once it is ready, and a `res://itest.tscn` scene containing
+//! only that runner. Launching that scene instead of the main one runs all tests, prints the results and quits; the exit code is the
+//! number of failed tests. No window or GPU is needed:
+//!
+//! ```text
+//! godot --headless --fixed-fps 60 --path dodge-the-creeps/godot res://itest.tscn
+//! ```
+//!
+//! Tests are `async`, so they can let the game run for a number of physics frames between their steps. `--fixed-fps` makes every frame
+//! advance the game by the same amount of time, no matter how fast the machine is.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use godot::classes::Engine;
+use godot::obj::Inherits;
+use godot::prelude::*;
+
+/// Outcome of a test; the error describes the failed check.
+pub type TestResult = Result<(), String>;
+
+pub type TestFuture = Pin<Box<dyn Future<Output = TestResult>>>;
+
+/// A named test. `run` usually wraps an `async move` block in `Box::pin()`.
+pub struct TestCase {
+    pub name: &'static str,
+    pub run: fn(TestContext) -> TestFuture,
+}
+
+/// Fails the current test with a message, unless the condition holds.
+#[macro_export]
+macro_rules! check {
+    ($condition:expr, $($message:tt)+) => {
+        if !$condition {
+            return Err(format!("{} ({}:{})", format_args!($($message)+), file!(), line!()));
+        }
+    };
+}
+
+/// Fails the current test unless both values are equal.
+#[macro_export]
+macro_rules! check_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        // Matching keeps temporaries such as bind() guards alive for the whole comparison.
+        match (&$actual, &$expected) {
+            (actual, expected) => {
+                $crate::check!(
+                    actual == expected,
+                    "`{}` is {:?}, expected {:?}",
+                    stringify!($actual),
+                    actual,
+                    expected
+                );
+            }
+        }
+    };
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Handed to every test, to set up scenes and let time pass.
+#[derive(Clone)]
+pub struct TestContext {
+    root: Gd<Node>,
+}
+
+impl TestContext {
+    /// Instantiates a scene and adds it to the tree, which runs its `ready()`. It is freed once the test ends.
+    pub fn add_scene<T>(&self, path: &str) -> Gd<T>
+    where
+        T: GodotClass + Inherits<Node>,
+    {
+        self.add_scene_with(path, |_| {})
+    }
+
+    /// Like [`add_scene()`](Self::add_scene), but runs `setup` before the scene enters the tree, e.g. to change exported properties
+    /// that `ready()` depends on.
+    pub fn add_scene_with<T>(&self, path: &str, setup: impl FnOnce(&mut Gd<T>)) -> Gd<T>
+    where
+        T: GodotClass + Inherits<Node>,
+    {
+        let mut scene = load::<PackedScene>(path).instantiate_as::<T>();
+        setup(&mut scene);
+        self.root.clone().add_child(&scene.clone().upcast::<Node>());
+
+        scene
+    }
+
+    pub fn tree(&self) -> Gd<SceneTree> {
+        self.root.get_tree()
+    }
+
+    /// Lets the game run for `count` physics frames. Time passes even while the tree is paused.
+    pub async fn physics_frames(&self, count: u32) {
+        let tree = self.tree();
+        for _ in 0..count {
+            tree.signals().physics_frame().to_future().await;
+        }
+    }
+
+    /// Lets the game run for the physics frames corresponding to `seconds`.
+    pub async fn seconds(&self, seconds: f64) {
+        self.physics_frames(seconds_to_frames(seconds)).await;
+    }
+
+    /// Waits until `condition` holds, checking once per physics frame. Fails if it still doesn't hold after `timeout_seconds`.
+    pub async fn wait_until(
+        &self,
+        timeout_seconds: f64,
+        description: &str,
+        mut condition: impl FnMut() -> bool,
+    ) -> TestResult {
+        for _ in 0..seconds_to_frames(timeout_seconds) {
+            if condition() {
+                return Ok(());
+            }
+            self.physics_frames(1).await;
+        }
+
+        if condition() {
+            Ok(())
+        } else {
+            Err(format!(
+                "timed out after {timeout_seconds}s waiting for: {description}"
+            ))
+        }
+    }
+
+    // Leaves a clean tree for the next test, even if this one failed halfway.
+    async fn tear_down(&self) {
+        let mut tree = self.tree();
+        tree.set_pause(false);
+
+        for mut child in self.root.get_children().iter_shared() {
+            child.queue_free();
+        }
+
+        self.physics_frames(1).await;
+    }
+}
+
+fn seconds_to_frames(seconds: f64) -> u32 {
+    let ticks_per_second = Engine::singleton().get_physics_ticks_per_second();
+    (seconds * ticks_per_second as f64).ceil() as u32
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Runs `tests` one after another, with scenes added below `root`, then quits the game.
+///
+/// The exit code is the number of failed tests, so scripts and CI can tell whether the run succeeded.
+pub fn run_tests(root: Gd<Node>, tests: &'static [TestCase]) {
+    godot::task::spawn(async move {
+        let ctx = TestContext { root };
+
+        // The runner is still in the middle of entering the tree, where children cannot be added yet.
+        ctx.physics_frames(1).await;
+
+        godot_print!("Running {} gameplay tests...", tests.len());
+
+        let mut failed = 0;
+        for test in tests {
+            let result = (test.run)(ctx.clone()).await;
+            ctx.tear_down().await;
+
+            match result {
+                Ok(()) => godot_print!("  {} ... ok", test.name),
+                Err(message) => {
+                    failed += 1;
+                    godot_print!("  {} ... FAILED\n    {message}", test.name);
+                }
+            }
+        }
+
+        godot_print!(
+            "Gameplay tests: {} passed, {failed} failed.",
+            tests.len() - failed
+        );
+
+        ctx.tree().quit_ex().exit_code(failed as i32).done();
+    });
+}
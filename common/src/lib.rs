@@ -0,0 +1,18 @@
+/*
+ * Copyright (c) godot-rust; Bromeon and contributors.
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Building blocks shared by several demos.
+//!
+//! Classes declared here are registered by every GDExtension library that uses them, just like the demo's own classes.
+
+pub mod achievement_toast;
+pub mod achievements;
+pub mod itest;
+pub mod locale;
+pub mod settings;
+pub mod settings_panel;
+pub mod telemetry;
@@ -0,0 +1,50 @@
+use std::fmt::Display;
+
+use godot::classes::TranslationServer;
+use godot::prelude::*;
+
+/// Translates `key` into the current locale. Keys without a translation are returned unchanged.
+///
+/// Static texts don't need this: labels and buttons whose text is a translation key are translated by Godot, and also re-rendered when
+/// the locale changes. Only texts assembled in code have to be translated (and re-rendered) by hand.
+pub fn tr(key: &str) -> String {
+    TranslationServer::singleton().translate(key).to_string()
+}
+
+/// Translates `key`, and fills the `{name}` placeholders of the translation with `args`.
+///
+/// For example, with the English entry `msgid "LIVES"` / `msgstr "Lives: {lives}"`, `tr_format("LIVES", &[("lives", &3)])` gives
+/// `"Lives: 3"`.
+pub fn tr_format(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = tr(key);
+    for (name, value) in args {
+        text = text.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+
+    text
+}
+
+/// Locales for which translations are loaded, sorted and without duplicates.
+pub fn available_locales() -> Vec<String> {
+    let mut locales: Vec<String> = TranslationServer::singleton()
+        .get_loaded_locales()
+        .as_slice()
+        .iter()
+        .map(GString::to_string)
+        .collect();
+
+    locales.sort();
+    locales.dedup();
+    locales
+}
+
+/// Name of `locale` in its own language, e.g. "Deutsch" for `de`. Every catalog provides it as the `LANGUAGE_NAME` entry.
+pub fn language_name(locale: &str) -> String {
+    let server = TranslationServer::singleton();
+
+    server
+        .get_translation_object(locale)
+        .map(|translation| translation.get_message("LANGUAGE_NAME").to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| server.get_locale_name(locale).to_string())
+}
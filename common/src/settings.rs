@@ -0,0 +1,386 @@
+use std::collections::BTreeMap;
+
+use godot::classes::display_server::WindowMode;
+use godot::classes::{
+    AudioServer, ConfigFile, DisplayServer, FileAccess, InputEvent, InputEventJoypadButton,
+    InputEventJoypadMotion, InputEventKey, InputMap, TranslationServer,
+};
+use godot::global::{db_to_linear, linear_to_db, Error, Key};
+use godot::prelude::*;
+
+const SAVE_PATH: &str = "user://settings.cfg";
+
+// Bump this whenever the layout of the settings file changes. Files with a different version are discarded.
+const SAVE_VERSION: i64 = 1;
+
+// Stick tilt needed before a motion is accepted as a new binding.
+const AXIS_CAPTURE_THRESHOLD: f32 = 0.5;
+
+/// Kind of device an input binding belongs to.
+///
+/// Every action can have several bindings of each kind. Rebinding replaces all bindings of one kind, and leaves the other one alone.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BindingKind {
+    Keyboard,
+    Gamepad,
+}
+
+impl BindingKind {
+    pub fn of(event: &Gd<InputEvent>) -> Option<Self> {
+        if event.is_class("InputEventKey") {
+            Some(Self::Keyboard)
+        } else if event.is_class("InputEventJoypadButton")
+            || event.is_class("InputEventJoypadMotion")
+        {
+            Some(Self::Gamepad)
+        } else {
+            None
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// User preferences: audio volumes, window mode, language and input bindings, persisted in `user://settings.cfg`.
+///
+/// Only what the user changed is stored; everything else keeps the values from the project settings. Changes take effect immediately,
+/// but are only written to disk by [`save()`](Self::save).
+#[derive(Default)]
+pub struct Settings {
+    /// Linear volume between 0 and 1, by audio bus name.
+    volumes: BTreeMap<String, f32>,
+    /// Window mode chosen by the user; otherwise, the project's window mode stays.
+    fullscreen: Option<bool>,
+    /// Locale chosen by the user; otherwise, Godot picks one matching the OS language.
+    locale: Option<String>,
+    /// All events of each action that was rebound.
+    bindings: BTreeMap<String, Vec<Gd<InputEvent>>>,
+}
+
+impl Settings {
+    /// Loads the settings file. A missing or outdated file results in the project defaults.
+    ///
+    /// The loaded settings are not applied yet; see [`apply()`](Self::apply).
+    pub fn load() -> Self {
+        if !FileAccess::file_exists(SAVE_PATH) {
+            return Self::default();
+        }
+
+        let mut config = ConfigFile::new_gd();
+        if config.load(SAVE_PATH) != Error::OK {
+            godot_warn!("Could not read settings from {SAVE_PATH}, using defaults.");
+            return Self::default();
+        }
+
+        let version = config.get_value("meta", "version").try_to::<i64>().ok();
+        if version != Some(SAVE_VERSION) {
+            godot_warn!("Settings file {SAVE_PATH} has an unsupported version, discarding it.");
+            return Self::default();
+        }
+
+        let mut settings = Self::default();
+
+        if config.has_section("audio") {
+            for bus in config.get_section_keys("audio").as_slice() {
+                if let Ok(volume) = config.get_value("audio", bus).try_to::<f32>() {
+                    settings
+                        .volumes
+                        .insert(bus.to_string(), volume.clamp(0.0, 1.0));
+                }
+            }
+        }
+
+        settings.fullscreen = config
+            .get_value("display", "fullscreen")
+            .try_to::<bool>()
+            .ok();
+
+        settings.locale = config
+            .get_value("interface", "locale")
+            .try_to::<GString>()
+            .ok()
+            .map(|locale| locale.to_string());
+
+        // Events are stored as arrays of serialized InputEvent objects, just like in project.godot.
+        if config.has_section("input") {
+            for action in config.get_section_keys("input").as_slice() {
+                let Ok(events) = config.get_value("input", action).try_to::<Array<Variant>>()
+                else {
+                    continue;
+                };
+
+                let events = events
+                    .iter_shared()
+                    .filter_map(|event| event.try_to::<Gd<InputEvent>>().ok())
+                    .collect();
+
+                settings.bindings.insert(action.to_string(), events);
+            }
+        }
+
+        settings
+    }
+
+    pub fn save(&self) {
+        let mut config = ConfigFile::new_gd();
+        config.set_value("meta", "version", &SAVE_VERSION.to_variant());
+
+        for (bus, volume) in &self.volumes {
+            config.set_value("audio", bus.as_str(), &volume.to_variant());
+        }
+
+        if let Some(fullscreen) = self.fullscreen {
+            config.set_value("display", "fullscreen", &fullscreen.to_variant());
+        }
+
+        if let Some(locale) = &self.locale {
+            config.set_value("interface", "locale", &locale.to_variant());
+        }
+
+        for (action, events) in &self.bindings {
+            let events: Array<Variant> = events.iter().map(|event| event.to_variant()).collect();
+            config.set_value("input", action.as_str(), &events.to_variant());
+        }
+
+        if config.save(SAVE_PATH) != Error::OK {
+            godot_warn!("Could not write settings to {SAVE_PATH}.");
+        }
+    }
+
+    /// Pushes all settings to the engine: audio buses, window and input map.
+    pub fn apply(&self) {
+        for (bus, volume) in &self.volumes {
+            apply_volume(bus, *volume);
+        }
+
+        if let Some(fullscreen) = self.fullscreen {
+            apply_fullscreen(fullscreen);
+        }
+
+        if let Some(locale) = &self.locale {
+            TranslationServer::singleton().set_locale(locale.as_str());
+        }
+
+        let mut input_map = InputMap::singleton();
+        for (action, events) in &self.bindings {
+            if !input_map.has_action(action.as_str()) {
+                godot_warn!(
+                    "Settings contain bindings for unknown action '{action}', ignoring them."
+                );
+                continue;
+            }
+
+            input_map.action_erase_events(action.as_str());
+            for event in events {
+                input_map.action_add_event(action.as_str(), event);
+            }
+        }
+    }
+
+    /// Linear volume between 0 and 1 of an audio bus.
+    pub fn volume(&self, bus: &str) -> f32 {
+        if let Some(volume) = self.volumes.get(bus) {
+            return *volume;
+        }
+
+        let audio = AudioServer::singleton();
+        let index = audio.get_bus_index(bus);
+        if index < 0 {
+            return 1.0;
+        }
+
+        db_to_linear(audio.get_bus_volume_db(index) as f64) as f32
+    }
+
+    pub fn set_volume(&mut self, bus: &str, volume: f32) {
+        let volume = volume.clamp(0.0, 1.0);
+
+        self.volumes.insert(bus.to_string(), volume);
+        apply_volume(bus, volume);
+    }
+
+    /// Whether the window is currently fullscreen, be it by the user's choice or the project settings.
+    pub fn fullscreen(&self) -> bool {
+        is_fullscreen()
+    }
+
+    pub fn set_fullscreen(&mut self, fullscreen: bool) {
+        self.fullscreen = Some(fullscreen);
+        apply_fullscreen(fullscreen);
+    }
+
+    /// Current locale, e.g. `"en"`.
+    pub fn locale(&self) -> String {
+        TranslationServer::singleton().get_locale().to_string()
+    }
+
+    /// Switches the language. Godot notifies all nodes, so that translated texts are re-rendered.
+    pub fn set_locale(&mut self, locale: &str) {
+        self.locale = Some(locale.to_string());
+        TranslationServer::singleton().set_locale(locale);
+    }
+
+    /// Replaces the bindings of `action` of the same kind as `captured` with that event.
+    ///
+    /// Returns false if `captured` cannot be used as a binding, for example a mouse event, a released key or a barely tilted stick.
+    pub fn rebind(&mut self, action: &str, captured: &Gd<InputEvent>) -> bool {
+        let mut input_map = InputMap::singleton();
+        if !input_map.has_action(action) {
+            godot_warn!("Cannot rebind unknown action '{action}'.");
+            return false;
+        }
+
+        let Some(kind) = BindingKind::of(captured) else {
+            return false;
+        };
+
+        let mut events: Vec<Gd<InputEvent>> =
+            input_map.action_get_events(action).iter_shared().collect();
+
+        // Gamepad bindings keep the device of the previous binding, so that per-player actions stay bound to their own gamepad.
+        let device = events
+            .iter()
+            .find(|event| BindingKind::of(event) == Some(kind))
+            .map_or(-1, |event| event.get_device());
+
+        let Some(binding) = to_binding(captured, device) else {
+            return false;
+        };
+
+        events.retain(|event| BindingKind::of(event) != Some(kind));
+        events.push(binding);
+
+        input_map.action_erase_events(action);
+        for event in &events {
+            input_map.action_add_event(action, event);
+        }
+
+        self.bindings.insert(action.to_string(), events);
+        true
+    }
+
+    /// Restores the input bindings from the project settings.
+    pub fn reset_bindings(&mut self) {
+        InputMap::singleton().load_from_project_settings();
+        self.bindings.clear();
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Short, human-readable description of the bindings of `action` of one kind, e.g. `"A, Left"` or `"Button 13, Axis 0-"`.
+pub fn binding_text(action: &str, kind: BindingKind) -> String {
+    let input_map = InputMap::singleton();
+    if !input_map.has_action(action) {
+        return String::new();
+    }
+
+    let texts: Vec<String> = input_map
+        .action_get_events(action)
+        .iter_shared()
+        .filter(|event| BindingKind::of(event) == Some(kind))
+        .map(|event| event_text(&event))
+        .collect();
+
+    if texts.is_empty() {
+        "-".to_string()
+    } else {
+        texts.join(", ")
+    }
+}
+
+fn event_text(event: &Gd<InputEvent>) -> String {
+    if let Ok(button) = event.clone().try_cast::<InputEventJoypadButton>() {
+        return format!("Button {}", button.get_button_index().ord());
+    }
+
+    if let Ok(motion) = event.clone().try_cast::<InputEventJoypadMotion>() {
+        let sign = if motion.get_axis_value() < 0.0 {
+            '-'
+        } else {
+            '+'
+        };
+        return format!("Axis {}{sign}", motion.get_axis().ord());
+    }
+
+    // Keys bound by physical location are described as e.g. "A (Physical)".
+    let text = event.as_text().to_string();
+    text.trim_end_matches(" (Physical)").to_string()
+}
+
+/// Turns a captured event into a clean binding: pressed state, modifiers, pressure etc. are not part of it.
+fn to_binding(captured: &Gd<InputEvent>, device: i32) -> Option<Gd<InputEvent>> {
+    if let Ok(key) = captured.clone().try_cast::<InputEventKey>() {
+        if !key.is_pressed() || key.is_echo() {
+            return None;
+        }
+
+        // Bind the physical location, like the project's default bindings, so they work with any keyboard layout.
+        let mut binding = InputEventKey::new_gd();
+        if key.get_physical_keycode() != Key::NONE {
+            binding.set_physical_keycode(key.get_physical_keycode());
+        } else {
+            binding.set_keycode(key.get_keycode());
+        }
+
+        binding.set_device(-1);
+        return Some(binding.upcast());
+    }
+
+    if let Ok(button) = captured.clone().try_cast::<InputEventJoypadButton>() {
+        if !button.is_pressed() {
+            return None;
+        }
+
+        let mut binding = InputEventJoypadButton::new_gd();
+        binding.set_button_index(button.get_button_index());
+        binding.set_device(device);
+        return Some(binding.upcast());
+    }
+
+    if let Ok(motion) = captured.clone().try_cast::<InputEventJoypadMotion>() {
+        let value = motion.get_axis_value();
+        if value.abs() < AXIS_CAPTURE_THRESHOLD {
+            return None;
+        }
+
+        let mut binding = InputEventJoypadMotion::new_gd();
+        binding.set_axis(motion.get_axis());
+        binding.set_axis_value(value.signum());
+        binding.set_device(device);
+        return Some(binding.upcast());
+    }
+
+    None
+}
+
+fn apply_volume(bus: &str, volume: f32) {
+    let mut audio = AudioServer::singleton();
+
+    let index = audio.get_bus_index(bus);
+    if index < 0 {
+        godot_warn!("Settings contain a volume for unknown audio bus '{bus}', ignoring it.");
+        return;
+    }
+
+    audio.set_bus_volume_db(index, linear_to_db(volume as f64) as f32);
+}
+
+fn is_fullscreen() -> bool {
+    let mode = DisplayServer::singleton().window_get_mode();
+    mode == WindowMode::FULLSCREEN || mode == WindowMode::EXCLUSIVE_FULLSCREEN
+}
+
+fn apply_fullscreen(fullscreen: bool) {
+    // Only touch the window if needed, so that e.g. a maximized window stays maximized.
+    if is_fullscreen() == fullscreen {
+        return;
+    }
+
+    let mode = if fullscreen {
+        WindowMode::FULLSCREEN
+    } else {
+        WindowMode::WINDOWED
+    };
+    DisplayServer::singleton().window_set_mode(mode);
+}
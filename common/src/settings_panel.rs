@@ -0,0 +1,389 @@
+use crate::locale::{available_locales, language_name, tr, tr_format};
+use crate::settings::{binding_text, BindingKind, Settings};
+
+use godot::classes::box_container::AlignmentMode;
+use godot::classes::control::SizeFlags;
+use godot::classes::node::ProcessMode;
+use godot::classes::{
+    AudioServer, Button, CheckButton, GridContainer, HBoxContainer, HSlider, IPanelContainer,
+    InputEvent, InputEventKey, Label, MarginContainer, OptionButton, PanelContainer,
+    ScrollContainer, VBoxContainer,
+};
+use godot::global::{HorizontalAlignment, Key};
+use godot::prelude::*;
+
+// Slider for one audio bus.
+struct VolumeRow {
+    bus: GString,
+    label: Gd<Label>,
+    slider: Gd<HSlider>,
+}
+
+// One row of the controls table.
+struct BindingRow {
+    action: GString,
+    keyboard: Gd<Button>,
+    gamepad: Gd<Button>,
+}
+
+/// Menu to change the [`Settings`]: a volume slider per audio bus, a fullscreen toggle, the language, and rebinding of the listed input
+/// actions.
+///
+/// The panel builds its own content and loads and applies the settings file when it enters the tree, so games only need to add it to
+/// their UI, hidden, and call [`open()`](Self::open). While the panel is open, it is fully in charge of input.
+#[derive(GodotClass)]
+#[class(base=PanelContainer)]
+pub struct SettingsPanel {
+    /// Input actions that can be rebound, in display order.
+    #[export]
+    actions: PackedStringArray,
+
+    /// Whether the scene tree is paused while the panel is open. Useful for games that have no pause of their own.
+    #[export]
+    pause_while_open: bool,
+
+    settings: Settings,
+    volume_rows: Vec<VolumeRow>,
+    fullscreen_button: OnReady<Gd<CheckButton>>,
+    language_button: OnReady<Gd<OptionButton>>,
+    // Locale of each entry in the language button.
+    locales: Vec<String>,
+    rows: Vec<BindingRow>,
+    // Row and kind of binding currently waiting for an input event.
+    capture: Option<(usize, BindingKind)>,
+
+    base: Base<PanelContainer>,
+}
+
+#[godot_api]
+impl IPanelContainer for SettingsPanel {
+    fn init(base: Base<PanelContainer>) -> Self {
+        Self {
+            actions: PackedStringArray::new(),
+            pause_while_open: false,
+            settings: Settings::default(),
+            volume_rows: Vec::new(),
+            // Created in ready() together with the rest of the content.
+            fullscreen_button: OnReady::manual(),
+            language_button: OnReady::manual(),
+            locales: Vec::new(),
+            rows: Vec::new(),
+            capture: None,
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        // The game may be paused while the panel is open, or the panel may be opened from a pause menu.
+        self.base_mut().set_process_mode(ProcessMode::ALWAYS);
+        self.base_mut().hide();
+
+        self.settings = Settings::load();
+        self.settings.apply();
+
+        self.build_content();
+    }
+
+    // input() rather than unhandled_input(): a captured key must not also trigger buttons or game actions.
+    fn input(&mut self, event: Gd<InputEvent>) {
+        if !self.base().is_visible() {
+            return;
+        }
+
+        let Some((row, kind)) = self.capture else {
+            // Escape leaves the panel, unless it is being bound.
+            if is_escape(&event) {
+                self.base().get_viewport().unwrap().set_input_as_handled();
+                self.close();
+            }
+            return;
+        };
+
+        self.base().get_viewport().unwrap().set_input_as_handled();
+
+        // Only Escape cancels: gamepad buttons such as ui_cancel's B button should remain bindable.
+        if is_escape(&event) {
+            self.stop_capture();
+            return;
+        }
+
+        // Events of the other device kind, mouse movement, key releases etc. are swallowed, but don't end the capture.
+        if BindingKind::of(&event) != Some(kind) {
+            return;
+        }
+
+        let action = self.rows[row].action.to_string();
+        if self.settings.rebind(&action, &event) {
+            self.stop_capture();
+        }
+    }
+}
+
+#[godot_api]
+impl SettingsPanel {
+    // Emitted once the panel has been closed and the settings have been saved.
+    #[signal]
+    pub fn closed();
+
+    pub fn open(&mut self) {
+        self.refresh();
+        self.base_mut().show();
+
+        if self.pause_while_open {
+            self.base().get_tree().set_pause(true);
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.capture = None;
+        self.settings.save();
+        self.base_mut().hide();
+
+        if self.pause_while_open {
+            self.base().get_tree().set_pause(false);
+        }
+
+        self.signals().closed().emit();
+    }
+
+    fn build_content(&mut self) {
+        let panel = self.to_gd();
+
+        let mut content = VBoxContainer::new_alloc();
+        content.add_theme_constant_override("separation", 12);
+
+        let mut title = Label::new_alloc();
+        // Texts that are translation keys are translated (and updated on language changes) by Godot itself.
+        title.set_text("SETTINGS");
+        title.set_horizontal_alignment(HorizontalAlignment::CENTER);
+        content.add_child(&title);
+
+        // Audio: one slider per bus.
+        let audio = AudioServer::singleton();
+        for index in 0..audio.get_bus_count() {
+            let bus = audio.get_bus_name(index);
+
+            // Text is set in refresh(), since it has to be translated by hand.
+            let mut label = Label::new_alloc();
+            label.set_h_size_flags(SizeFlags::EXPAND_FILL);
+
+            let mut slider = HSlider::new_alloc();
+            slider.set_min(0.0);
+            slider.set_max(1.0);
+            slider.set_step(0.05);
+            slider.set_h_size_flags(SizeFlags::EXPAND_FILL);
+
+            let bus_name = bus.to_string();
+            slider.signals().value_changed().connect_other(
+                &panel,
+                move |this: &mut Self, value: f64| {
+                    this.settings.set_volume(&bus_name, value as f32);
+                },
+            );
+
+            let mut row = HBoxContainer::new_alloc();
+            row.add_child(&label);
+            row.add_child(&slider);
+            content.add_child(&row);
+
+            self.volume_rows.push(VolumeRow { bus, label, slider });
+        }
+
+        // Display.
+        let mut fullscreen_button = CheckButton::new_alloc();
+        fullscreen_button.set_text("FULLSCREEN");
+        fullscreen_button
+            .signals()
+            .toggled()
+            .connect_other(&panel, |this: &mut Self, on: bool| {
+                this.settings.set_fullscreen(on);
+            });
+        content.add_child(&fullscreen_button);
+        self.fullscreen_button.init(fullscreen_button);
+
+        // Language: one entry per loaded translation catalog, named in its own language.
+        let mut language_label = Label::new_alloc();
+        language_label.set_text("LANGUAGE");
+        language_label.set_h_size_flags(SizeFlags::EXPAND_FILL);
+
+        let mut language_button = OptionButton::new_alloc();
+        language_button.set_h_size_flags(SizeFlags::EXPAND_FILL);
+        self.locales = available_locales();
+        for locale in &self.locales {
+            language_button.add_item(language_name(locale).as_str());
+        }
+        language_button.signals().item_selected().connect_other(
+            &panel,
+            |this: &mut Self, index: i64| {
+                let Some(locale) = this.locales.get(index as usize).cloned() else {
+                    return;
+                };
+                this.settings.set_locale(&locale);
+                this.refresh();
+            },
+        );
+
+        let mut row = HBoxContainer::new_alloc();
+        row.add_child(&language_label);
+        row.add_child(&language_button);
+        content.add_child(&row);
+        self.language_button.init(language_button);
+
+        // Controls: action name, keyboard bindings, gamepad bindings.
+        let mut controls = GridContainer::new_alloc();
+        controls.set_columns(3);
+        controls.set_h_size_flags(SizeFlags::EXPAND_FILL);
+
+        for (index, action) in self.actions.as_slice().iter().enumerate() {
+            let mut label = Label::new_alloc();
+            label.set_text(action_label(&action.to_string()).as_str());
+            label.set_h_size_flags(SizeFlags::EXPAND_FILL);
+            controls.add_child(&label);
+
+            let keyboard = self.binding_button(index, BindingKind::Keyboard);
+            controls.add_child(&keyboard);
+
+            let gamepad = self.binding_button(index, BindingKind::Gamepad);
+            controls.add_child(&gamepad);
+
+            self.rows.push(BindingRow {
+                action: action.clone(),
+                keyboard,
+                gamepad,
+            });
+        }
+
+        let mut scroll = ScrollContainer::new_alloc();
+        scroll.set_v_size_flags(SizeFlags::EXPAND_FILL);
+        scroll.add_child(&controls);
+        content.add_child(&scroll);
+
+        // Buttons at the bottom.
+        let mut reset_button = Button::new_alloc();
+        reset_button.set_text("RESET_CONTROLS");
+        reset_button
+            .signals()
+            .pressed()
+            .connect_other(&panel, |this: &mut Self| {
+                this.settings.reset_bindings();
+                this.refresh();
+            });
+
+        let mut close_button = Button::new_alloc();
+        close_button.set_text("CLOSE");
+        close_button
+            .signals()
+            .pressed()
+            .connect_other(&panel, Self::close);
+
+        let mut buttons = HBoxContainer::new_alloc();
+        buttons.set_alignment(AlignmentMode::CENTER);
+        buttons.add_child(&reset_button);
+        buttons.add_child(&close_button);
+        content.add_child(&buttons);
+
+        let mut margin = MarginContainer::new_alloc();
+        for side in ["margin_left", "margin_right", "margin_top", "margin_bottom"] {
+            margin.add_theme_constant_override(side, 16);
+        }
+        margin.add_child(&content);
+
+        self.base_mut().add_child(&margin);
+    }
+
+    fn binding_button(&self, row: usize, kind: BindingKind) -> Gd<Button> {
+        let mut button = Button::new_alloc();
+        button.set_h_size_flags(SizeFlags::EXPAND_FILL);
+        button.set_clip_text(true);
+
+        button
+            .signals()
+            .pressed()
+            .connect_other(&self.to_gd(), move |this: &mut Self| {
+                this.start_capture(row, kind);
+            });
+
+        button
+    }
+
+    // Shows the current values, which may have been changed outside of the panel.
+    fn refresh(&mut self) {
+        for row in &mut self.volume_rows {
+            let bus = row.bus.to_string();
+            row.label
+                .set_text(tr_format("VOLUME", &[("bus", &bus)]).as_str());
+
+            let volume = self.settings.volume(&bus);
+            row.slider.set_value_no_signal(volume as f64);
+        }
+
+        let fullscreen = self.settings.fullscreen();
+        self.fullscreen_button.set_pressed_no_signal(fullscreen);
+
+        // The OS locale may be more specific than the catalogs, e.g. "en_US" while only "en" is loaded.
+        let locale = self.settings.locale();
+        let language = locale.split('_').next().unwrap_or_default();
+        let selected = self
+            .locales
+            .iter()
+            .position(|l| *l == locale)
+            .or_else(|| self.locales.iter().position(|l| l == language));
+        if let Some(index) = selected {
+            self.language_button.select(index as i32);
+        }
+
+        for row in &mut self.rows {
+            let action = row.action.to_string();
+            row.keyboard
+                .set_text(binding_text(&action, BindingKind::Keyboard).as_str());
+            row.gamepad
+                .set_text(binding_text(&action, BindingKind::Gamepad).as_str());
+        }
+    }
+
+    fn start_capture(&mut self, row: usize, kind: BindingKind) {
+        // Starting another capture cancels the previous one.
+        self.refresh();
+        self.capture = Some((row, kind));
+
+        let prompt = match kind {
+            BindingKind::Keyboard => "PRESS_KEY",
+            BindingKind::Gamepad => "PRESS_BUTTON",
+        };
+
+        let row = &mut self.rows[row];
+        match kind {
+            BindingKind::Keyboard => row.keyboard.set_text(prompt),
+            BindingKind::Gamepad => row.gamepad.set_text(prompt),
+        }
+    }
+
+    fn stop_capture(&mut self) {
+        self.capture = None;
+        self.refresh();
+    }
+}
+
+// Escape always cancels, even if it is not part of ui_cancel, so that keyboard capture can be left.
+fn is_escape(event: &Gd<InputEvent>) -> bool {
+    event
+        .clone()
+        .try_cast::<InputEventKey>()
+        .is_ok_and(|key| key.is_pressed() && key.get_keycode() == Key::ESCAPE)
+}
+
+// Translation key "ACTION_P1_MOVE_LEFT" if the catalogs have it; otherwise "p1_move_left" -> "P1 move left".
+fn action_label(action: &str) -> String {
+    let key = format!("ACTION_{}", action.to_uppercase());
+    if tr(&key) != key {
+        return key;
+    }
+
+    let text = action.replace('_', " ");
+
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => text,
+    }
+}
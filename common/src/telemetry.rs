@@ -0,0 +1,211 @@
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write as _};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use godot::classes::{Os, ProjectSettings, Time};
+use godot::prelude::*;
+
+/// Something that happened during a session, with any number of fields. Becomes one line of JSON in the log.
+///
+/// Built in one expression, e.g. `Event::new("spawn").with_str("kind", "Homing").with_float("speed", 212.5)`.
+pub struct Event {
+    // Comma-separated `"key":value` pairs, without braces.
+    fields: String,
+}
+
+impl Event {
+    pub fn new(name: &str) -> Self {
+        let mut fields = String::new();
+        push_json_string(&mut fields, "event");
+        fields.push(':');
+        push_json_string(&mut fields, name);
+
+        Self { fields }
+    }
+
+    pub fn with_int(mut self, key: &str, value: i64) -> Self {
+        self.push_key(key);
+        write!(self.fields, "{value}").unwrap();
+        self
+    }
+
+    /// NaN and infinite values are written as `null`, since JSON has no representation for them.
+    pub fn with_float(mut self, key: &str, value: f64) -> Self {
+        self.push_key(key);
+        if value.is_finite() {
+            write!(self.fields, "{value}").unwrap();
+        } else {
+            self.fields.push_str("null");
+        }
+        self
+    }
+
+    pub fn with_bool(mut self, key: &str, value: bool) -> Self {
+        self.push_key(key);
+        write!(self.fields, "{value}").unwrap();
+        self
+    }
+
+    pub fn with_str(mut self, key: &str, value: &str) -> Self {
+        self.push_key(key);
+        push_json_string(&mut self.fields, value);
+        self
+    }
+
+    fn push_key(&mut self, key: &str) {
+        self.fields.push(',');
+        push_json_string(&mut self.fields, key);
+        self.fields.push(':');
+    }
+}
+
+fn push_json_string(out: &mut String, text: &str) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Event log of one session, appended as JSON lines to a file in `user://`, for analysing game balance offline.
+///
+/// Every line carries the session (Unix time in milliseconds at which it started) and the seconds elapsed since then, for example:
+///
+/// ```text
+/// {"session":1760786400000,"time":12.345,"event":"hit","player":0,"lives":2}
+/// ```
+///
+/// Events are formatted on the calling thread, but written to disk by a background thread, so that file I/O never stalls a frame.
+/// Dropping the log waits until all pending events have been written.
+pub struct Telemetry {
+    // None if disabled, or if the file could not be opened.
+    sender: Option<Sender<String>>,
+    writer: Option<JoinHandle<()>>,
+    session: i64,
+    start_msec: u64,
+}
+
+impl Telemetry {
+    /// Starts a session that appends to `user://<file_name>`.
+    ///
+    /// If the file cannot be opened or no thread can be started (e.g. on the web), a warning is printed and events are discarded.
+    pub fn start(file_name: &str) -> Self {
+        let mut telemetry = Self::disabled();
+
+        let path = format!("user://{file_name}");
+        let os_path = ProjectSettings::singleton()
+            .globalize_path(path.as_str())
+            .to_string();
+
+        let file = match OpenOptions::new().create(true).append(true).open(&os_path) {
+            Ok(file) => file,
+            Err(err) => {
+                godot_warn!("Could not open telemetry log {path}: {err}");
+                return telemetry;
+            }
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        let spawned = thread::Builder::new()
+            .name("telemetry-writer".to_string())
+            .spawn(move || write_lines(file, receiver));
+
+        match spawned {
+            Ok(writer) => {
+                telemetry.sender = Some(sender);
+                telemetry.writer = Some(writer);
+            }
+            Err(err) => godot_warn!("Could not start telemetry writer thread: {err}"),
+        }
+
+        telemetry
+    }
+
+    /// Whether the game was started with `--telemetry` among the user arguments, e.g. for a balancing session:
+    ///
+    /// ```text
+    /// godot --path squash-the-creeps/godot -- --telemetry
+    /// ```
+    ///
+    /// Games keep telemetry off otherwise, since the log grows with every session.
+    pub fn requested_on_command_line() -> bool {
+        Os::singleton()
+            .get_cmdline_user_args()
+            .as_slice()
+            .iter()
+            .any(|arg| arg.to_string() == "--telemetry")
+    }
+
+    /// A log that discards all events.
+    pub fn disabled() -> Self {
+        let time = Time::singleton();
+
+        Self {
+            sender: None,
+            writer: None,
+            session: (time.get_unix_time_from_system() * 1000.0) as i64,
+            start_msec: time.get_ticks_msec(),
+        }
+    }
+
+    pub fn log(&self, event: Event) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        let elapsed_msec = Time::singleton()
+            .get_ticks_msec()
+            .saturating_sub(self.start_msec);
+
+        let line = format!(
+            "{{\"session\":{},\"time\":{:.3},{}}}",
+            self.session,
+            elapsed_msec as f64 / 1000.0,
+            event.fields
+        );
+
+        // Only fails if the writer gave up after an I/O error, which it has already reported.
+        let _ = sender.send(line);
+    }
+}
+
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        // Closing the channel ends the writer's loop, once everything sent so far is written.
+        self.sender = None;
+
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+// Runs on the writer thread, until the channel is closed.
+fn write_lines(file: File, receiver: Receiver<String>) {
+    let mut out = BufWriter::new(file);
+
+    // Block for the next line, then write everything that has queued up meanwhile, and flush once per batch.
+    while let Ok(line) = receiver.recv() {
+        let result = std::iter::once(line)
+            .chain(receiver.try_iter())
+            .try_for_each(|line| writeln!(out, "{line}"))
+            .and_then(|()| out.flush());
+
+        if let Err(err) = result {
+            godot_warn!("Telemetry log could not be written, no more events are recorded: {err}");
+            return;
+        }
+    }
+}
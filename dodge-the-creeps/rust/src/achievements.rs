@@ -0,0 +1,34 @@
+use demo_common::achievements::Achievement;
+
+// Checked by Main as the corresponding gameplay events arrive.
+// Titles and descriptions are translation keys, see the catalogs in translations/.
+
+pub const SURVIVOR: Achievement = Achievement {
+    id: "survive_60s",
+    title: "ACH_SURVIVOR",
+    description: "ACH_SURVIVOR_DESC",
+};
+
+pub const CENTURY: Achievement = Achievement {
+    id: "score_100",
+    title: "ACH_CENTURY",
+    description: "ACH_CENTURY_DESC",
+};
+
+pub const UNTOUCHABLE: Achievement = Achievement {
+    id: "score_50_no_hit",
+    title: "ACH_UNTOUCHABLE",
+    description: "ACH_UNTOUCHABLE_DESC",
+};
+
+pub const DAREDEVIL: Achievement = Achievement {
+    id: "max_combo",
+    title: "ACH_DAREDEVIL",
+    description: "ACH_DAREDEVIL_DESC",
+};
+
+pub const GIANT_DODGER: Achievement = Achievement {
+    id: "outlast_boss",
+    title: "ACH_GIANT_DODGER",
+    description: "ACH_GIANT_DODGER_DESC",
+};
@@ -0,0 +1,167 @@
+use godot::classes::camera_2d::AnchorMode;
+use godot::classes::control::{LayoutPreset, MouseFilter};
+use godot::classes::{Camera2D, CanvasLayer, ColorRect, Engine, FastNoiseLite, ICamera2D, Time};
+use godot::prelude::*;
+
+/// Camera with "juice" effects, for hits and bonuses: screen shake, hit-stop and full-screen flashes.
+///
+/// Shake is driven by _trauma_ between 0 and 1: events add trauma, which decays over time, and the screen shakes with the square of it.
+/// This makes small bumps subtle, while several hits in a row escalate quickly.
+///
+/// The camera keeps the default view (origin in the top-left corner), so it can be added to any 2D scene without moving things around.
+#[derive(GodotClass)]
+#[class(base=Camera2D)]
+pub struct CameraEffects {
+    /// Offset in pixels at full trauma, per axis.
+    #[export]
+    max_offset: Vector2,
+
+    /// Trauma lost per second.
+    #[export]
+    trauma_decay: f32,
+
+    /// How fast the shake moves; higher values are more jittery.
+    #[export]
+    shake_frequency: f32,
+
+    /// Game speed during a hit-stop; 0 freezes the game completely.
+    #[export(range = (0.0, 1.0))]
+    hit_stop_time_scale: f64,
+
+    /// Seconds until a flash has faded out.
+    #[export]
+    flash_duration: f64,
+
+    trauma: f32,
+    // Engine ticks at the last process() call, to measure real time regardless of the time scale.
+    last_ticks_usec: u64,
+    // Advances in real time, to sample the noise.
+    shake_time: f32,
+    noise: Gd<FastNoiseLite>,
+    // Physics frames left of the current hit-stop.
+    hit_stop_frames: u32,
+    flash_color: Color,
+    // Seconds left of the current flash.
+    flash_left: f64,
+    flash_rect: OnReady<Gd<ColorRect>>,
+    base: Base<Camera2D>,
+}
+
+#[godot_api]
+impl ICamera2D for CameraEffects {
+    fn init(base: Base<Camera2D>) -> Self {
+        Self {
+            max_offset: Vector2::new(16.0, 12.0),
+            trauma_decay: 1.2,
+            shake_frequency: 25.0,
+            hit_stop_time_scale: 0.05,
+            flash_duration: 0.25,
+            trauma: 0.0,
+            last_ticks_usec: 0,
+            shake_time: 0.0,
+            noise: FastNoiseLite::new_gd(),
+            hit_stop_frames: 0,
+            flash_color: Color::TRANSPARENT_WHITE,
+            flash_left: 0.0,
+            // Created in ready(), together with its layer.
+            flash_rect: OnReady::manual(),
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        self.base_mut().set_anchor_mode(AnchorMode::FIXED_TOP_LEFT);
+
+        // Own layer above everything else, so that flashes also cover the HUD.
+        let mut layer = CanvasLayer::new_alloc();
+        layer.set_layer(10);
+
+        let mut flash_rect = ColorRect::new_alloc();
+        flash_rect.set_anchors_preset(LayoutPreset::FULL_RECT);
+        flash_rect.set_mouse_filter(MouseFilter::IGNORE);
+        flash_rect.set_color(Color::TRANSPARENT_WHITE);
+
+        layer.add_child(&flash_rect);
+        self.base_mut().add_child(&layer);
+        self.flash_rect.init(flash_rect);
+
+        self.last_ticks_usec = Time::singleton().get_ticks_usec();
+    }
+
+    // Shake and flash are purely visual and run in real time, so that they don't stall during a hit-stop.
+    fn process(&mut self, _delta: f64) {
+        let ticks_usec = Time::singleton().get_ticks_usec();
+        let real_delta = ticks_usec.saturating_sub(self.last_ticks_usec) as f64 / 1_000_000.0;
+        self.last_ticks_usec = ticks_usec;
+
+        self.trauma = (self.trauma - self.trauma_decay * real_delta as f32).max(0.0);
+        self.shake_time += self.shake_frequency * real_delta as f32;
+
+        let shake = self.trauma * self.trauma;
+        let offset = Vector2::new(
+            self.max_offset.x * shake * self.noise.get_noise_2d(self.shake_time, 0.0),
+            self.max_offset.y * shake * self.noise.get_noise_2d(self.shake_time, 100.0),
+        );
+        self.base_mut().set_offset(offset);
+
+        if self.flash_left > 0.0 {
+            self.flash_left = (self.flash_left - real_delta).max(0.0);
+
+            let mut color = self.flash_color;
+            color.a *= (self.flash_left / self.flash_duration.max(f64::EPSILON)) as f32;
+            self.flash_rect.set_color(color);
+        }
+    }
+
+    // Hit-stops are counted in physics frames rather than seconds, so that replays slow down at exactly the same frames as the
+    // recorded run.
+    fn physics_process(&mut self, _delta: f64) {
+        if self.hit_stop_frames == 0 {
+            return;
+        }
+
+        self.hit_stop_frames -= 1;
+        if self.hit_stop_frames == 0 {
+            Engine::singleton().set_time_scale(1.0);
+        }
+    }
+
+    fn exit_tree(&mut self) {
+        // Don't leave the whole engine in slow motion.
+        if self.hit_stop_frames > 0 {
+            self.hit_stop_frames = 0;
+            Engine::singleton().set_time_scale(1.0);
+        }
+    }
+}
+
+#[godot_api]
+impl CameraEffects {
+    /// Shakes the screen. `amount` between 0 and 1 adds up with the trauma left from earlier events.
+    #[func]
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    /// Slows the game down to `hit_stop_time_scale` for `seconds` of real time.
+    #[func]
+    pub fn hit_stop(&mut self, seconds: f64) {
+        let ticks_per_second = Engine::singleton().get_physics_ticks_per_second() as f64;
+        let frames = (seconds * ticks_per_second).round() as u32;
+        if frames == 0 {
+            return;
+        }
+
+        // Overlapping hit-stops don't add up, the longer one wins.
+        self.hit_stop_frames = self.hit_stop_frames.max(frames);
+        Engine::singleton().set_time_scale(self.hit_stop_time_scale);
+    }
+
+    /// Flashes the whole screen in `color`, fading out over `flash_duration`. The alpha of `color` is the initial opacity.
+    #[func]
+    pub fn flash(&mut self, color: Color) {
+        self.flash_color = color;
+        self.flash_left = self.flash_duration;
+        self.flash_rect.set_color(color);
+    }
+}
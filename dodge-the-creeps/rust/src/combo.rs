@@ -0,0 +1,46 @@
+// Points for a single near miss, before any multipliers.
+pub const NEAR_MISS_POINTS: i64 = 2;
+
+// The combo multiplier does not grow beyond this.
+pub const MAX_MULTIPLIER: i64 = 5;
+
+// Without a near miss for this many seconds, the combo drops by one step.
+const DECAY_TIME: f64 = 2.0;
+
+/// Chain of near misses of one player.
+///
+/// Every near miss raises the multiplier by one, up to a maximum. Without further near misses, it decays step by step back to 1.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct Combo {
+    // Near misses in the chain, capped at the maximum multiplier.
+    chain: i64,
+    // Seconds until the next decay step.
+    decay: f64,
+}
+
+impl Combo {
+    /// Extends the chain and returns the multiplier for this near miss.
+    pub fn register_near_miss(&mut self) -> i64 {
+        self.chain = (self.chain + 1).min(MAX_MULTIPLIER);
+        self.decay = DECAY_TIME;
+
+        self.multiplier()
+    }
+
+    /// Counts down towards the next decay step.
+    pub fn tick(&mut self, delta: f64) {
+        if self.chain == 0 {
+            return;
+        }
+
+        self.decay -= delta;
+        if self.decay <= 0.0 {
+            self.chain -= 1;
+            self.decay = DECAY_TIME;
+        }
+    }
+
+    pub fn multiplier(&self) -> i64 {
+        self.chain.max(1)
+    }
+}
@@ -0,0 +1,135 @@
+use std::cell::OnceCell;
+
+use godot::prelude::*;
+
+/// Spawn parameters at a given point in a run.
+#[derive(Copy, Clone, Debug)]
+pub struct Difficulty {
+    /// Seconds between two mob spawns.
+    pub spawn_interval: f64,
+    pub min_speed: real,
+    pub max_speed: real,
+    /// Maximum deviation of a mob's direction from the inward normal of the spawn path, in radians.
+    pub spread: f32,
+}
+
+impl Difficulty {
+    fn lerp(&self, to: &Self, weight: f32) -> Self {
+        Self {
+            spawn_interval: self.spawn_interval
+                + (to.spawn_interval - self.spawn_interval) * weight as f64,
+            min_speed: self.min_speed + (to.min_speed - self.min_speed) * weight as real,
+            max_speed: self.max_speed + (to.max_speed - self.max_speed) * weight as real,
+            spread: self.spread + (to.spread - self.spread) * weight,
+        }
+    }
+}
+
+impl Default for Difficulty {
+    // Same values as the original, flat game.
+    fn default() -> Self {
+        Self {
+            spawn_interval: 0.5,
+            min_speed: 150.0,
+            max_speed: 250.0,
+            spread: std::f32::consts::FRAC_PI_4,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Spawn parameters that are reached once the score hits a certain value.
+#[derive(GodotClass)]
+#[class(init, base=Resource)]
+pub struct DifficultyKeyframe {
+    /// Score at which these values apply.
+    #[export]
+    score: i64,
+
+    /// Seconds between two mob spawns.
+    #[export]
+    #[init(val = 0.5)]
+    spawn_interval: f64,
+
+    /// Minimum speed of a spawned mob, in pixels per second.
+    #[export]
+    #[init(val = 150.0)]
+    min_speed: real,
+
+    /// Maximum speed of a spawned mob, in pixels per second.
+    #[export]
+    #[init(val = 250.0)]
+    max_speed: real,
+
+    /// Maximum deviation of a mob's direction from the straight path into the screen, in degrees.
+    #[export]
+    #[init(val = 45.0)]
+    spread_degrees: f32,
+
+    base: Base<Resource>,
+}
+
+impl DifficultyKeyframe {
+    fn to_difficulty(&self) -> Difficulty {
+        Difficulty {
+            spawn_interval: self.spawn_interval.max(0.05),
+            min_speed: self.min_speed,
+            max_speed: self.max_speed.max(self.min_speed),
+            spread: self.spread_degrees.abs().to_radians(),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Describes how the game gets harder as the score rises.
+///
+/// Values are linearly interpolated between the two keyframes surrounding the current score. Below the first keyframe, its values apply;
+/// beyond the last keyframe, the difficulty stays constant.
+#[derive(GodotClass)]
+#[class(init, base=Resource)]
+pub struct DifficultyCurve {
+    /// Keyframes of the curve. Order does not matter, they are sorted by score. Empty slots are skipped.
+    #[export]
+    keyframes: Array<Option<Gd<DifficultyKeyframe>>>,
+
+    // Keyframes converted and sorted by score. Built on the first sample, since the curve does not change during a game.
+    sorted: OnceCell<Vec<(i64, Difficulty)>>,
+
+    base: Base<Resource>,
+}
+
+impl DifficultyCurve {
+    pub fn sample(&self, score: i64) -> Difficulty {
+        let keyframes = self.sorted.get_or_init(|| {
+            let mut keyframes: Vec<(i64, Difficulty)> = self
+                .keyframes
+                .iter_shared()
+                .flatten()
+                .map(|keyframe| {
+                    let keyframe = keyframe.bind();
+                    (keyframe.score, keyframe.to_difficulty())
+                })
+                .collect();
+
+            keyframes.sort_by_key(|(score, _)| *score);
+            keyframes
+        });
+
+        // Index of the first keyframe above the current score.
+        let next = keyframes.partition_point(|(at, _)| *at <= score);
+
+        match (next.checked_sub(1), keyframes.get(next)) {
+            (None, None) => Difficulty::default(),
+            (None, Some((_, first))) => *first,
+            (Some(prev), None) => keyframes[prev].1,
+            (Some(prev), Some((to_score, to))) => {
+                let (from_score, from) = keyframes[prev];
+                let weight = (score - from_score) as f32 / (to_score - from_score) as f32;
+
+                from.lerp(to, weight)
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+/// Phases of the game, driven by `Main`.
+///
+/// Valid transitions:
+/// - `Title` -> `Countdown`: the start button was pressed.
+/// - `Countdown` -> `Playing`: the start timer ran out.
+/// - `Countdown`/`Playing` -> `Paused` -> back to the same state: the pause action was pressed.
+/// - `Countdown`/`Playing` -> `GameOver`: the player was hit.
+/// - `GameOver` -> `Title`: after a short delay.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameState {
+    /// Title screen with start button and leaderboard.
+    Title,
+    /// "Get Ready": the player can move, but no mobs spawn yet.
+    Countdown,
+    /// Mobs spawn and the score rises.
+    Playing,
+    /// The scene tree is frozen; resumes to the state it was paused from.
+    Paused,
+    /// The player was hit; the title screen follows after a short delay.
+    GameOver,
+}
+
+impl GameState {
+    /// Whether the state machine may go directly from `self` to `next`.
+    pub fn can_transition_to(self, next: Self) -> bool {
+        use GameState::*;
+
+        matches!(
+            (self, next),
+            (Title, Countdown)
+                | (Countdown, Playing)
+                | (Countdown | Playing, Paused)
+                | (Paused, Countdown | Playing)
+                | (Countdown | Playing, GameOver)
+                | (GameOver, Title)
+        )
+    }
+
+    /// Whether the pause action has an effect in this state.
+    pub fn is_pausable(self) -> bool {
+        matches!(self, Self::Countdown | Self::Playing)
+    }
+}
@@ -0,0 +1,149 @@
+use godot::classes::{ConfigFile, FileAccess, Time};
+use godot::global::Error;
+use godot::prelude::*;
+
+const SAVE_PATH: &str = "user://high_scores.cfg";
+
+// Bump this whenever the layout of the save file changes. Files with a different version are discarded.
+const SAVE_VERSION: i64 = 1;
+
+// How many runs are kept in the table.
+const CAPACITY: usize = 5;
+
+/// A single finished run.
+#[derive(Clone, Debug)]
+pub struct ScoreEntry {
+    pub score: i64,
+    /// Date of the run, formatted as `YYYY-MM-DD`.
+    pub date: GString,
+    /// Time survived, in seconds.
+    pub duration: f64,
+}
+
+impl ScoreEntry {
+    /// Creates an entry for a run that ended just now.
+    pub fn new(score: i64, duration: f64) -> Self {
+        Self {
+            score,
+            date: Time::singleton().get_date_string_from_system(),
+            duration,
+        }
+    }
+}
+
+/// Top runs, persisted to `user://` between sessions.
+///
+/// This is a plain Rust struct rather than a Godot class: only `Main` owns it, and nothing needs to access it from GDScript.
+#[derive(Default)]
+pub struct HighScores {
+    entries: Vec<ScoreEntry>,
+    /// Whether the table is saved to disk; see [`in_memory()`](Self::in_memory).
+    persistent: bool,
+}
+
+impl HighScores {
+    /// Loads the table from disk. A missing or outdated file results in an empty table.
+    pub fn load() -> Self {
+        let empty = Self {
+            persistent: true,
+            ..Self::default()
+        };
+
+        if !FileAccess::file_exists(SAVE_PATH) {
+            return empty;
+        }
+
+        let mut config = ConfigFile::new_gd();
+        if config.load(SAVE_PATH) != Error::OK {
+            godot_warn!(
+                "Could not read high scores from {SAVE_PATH}, starting with an empty table."
+            );
+            return empty;
+        }
+
+        let version = config.get_value("meta", "version").try_to::<i64>().ok();
+        if version != Some(SAVE_VERSION) {
+            godot_warn!("High score file {SAVE_PATH} has an unsupported version, discarding it.");
+            return empty;
+        }
+
+        // Each run is stored in its own section: [entry_0], [entry_1], ...
+        let mut entries = Vec::new();
+        for i in 0..CAPACITY {
+            let section = format!("entry_{i}");
+            if !config.has_section(section.as_str()) {
+                break;
+            }
+
+            let score = config.get_value(section.as_str(), "score").try_to::<i64>();
+            let date = config
+                .get_value(section.as_str(), "date")
+                .try_to::<GString>();
+            let duration = config
+                .get_value(section.as_str(), "duration")
+                .try_to::<f64>();
+
+            if let (Ok(score), Ok(date), Ok(duration)) = (score, date, duration) {
+                entries.push(ScoreEntry {
+                    score,
+                    date,
+                    duration,
+                });
+            }
+        }
+
+        Self {
+            entries,
+            persistent: true,
+        }
+    }
+
+    /// An empty table that is never saved, e.g. for tests that must not touch the player's high scores.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Best runs, highest score first.
+    pub fn entries(&self) -> &[ScoreEntry] {
+        &self.entries
+    }
+
+    /// Inserts a finished run and saves the table (unless in memory).
+    ///
+    /// Returns the 0-based rank of the run, or `None` if it didn't make it into the table.
+    pub fn submit(&mut self, entry: ScoreEntry) -> Option<usize> {
+        if entry.score <= 0 {
+            return None;
+        }
+
+        // Insert after all entries with an equal or higher score, so older runs win ties.
+        let rank = self.entries.partition_point(|e| e.score >= entry.score);
+        if rank >= CAPACITY {
+            return None;
+        }
+
+        self.entries.insert(rank, entry);
+        self.entries.truncate(CAPACITY);
+        if self.persistent {
+            self.save();
+        }
+
+        Some(rank)
+    }
+
+    fn save(&self) {
+        let mut config = ConfigFile::new_gd();
+        config.set_value("meta", "version", &SAVE_VERSION.to_variant());
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let section = format!("entry_{i}");
+            config.set_value(section.as_str(), "score", &entry.score.to_variant());
+            config.set_value(section.as_str(), "date", &entry.date.to_variant());
+            config.set_value(section.as_str(), "duration", &entry.duration.to_variant());
+        }
+
+        if config.save(SAVE_PATH) != Error::OK {
+            godot_warn!("Could not write high scores to {SAVE_PATH}.");
+        }
+    }
+}
@@ -1,70 +1,366 @@
-use godot::classes::{Button, CanvasLayer, ICanvasLayer, Label, Timer};
+use crate::high_scores::ScoreEntry;
+use crate::node_refs::NodeRefs;
+use crate::power_up::ActiveEffects;
+use crate::score_popup::ScorePopup;
+
+use demo_common::achievement_toast::AchievementToast;
+use demo_common::achievements::Achievement;
+use demo_common::locale::{tr, tr_format};
+use demo_common::settings_panel::SettingsPanel;
+use godot::classes::node::ProcessMode;
+use godot::classes::notify::NodeNotification;
+use godot::classes::{
+    Button, CanvasLayer, ColorRect, Font, ICanvasLayer, InputEvent, Label, Timer,
+};
+use godot::global::HorizontalAlignment;
 use godot::prelude::*;
 
+// Popups of long combos are drawn in this color; a single near miss is white.
+const MAX_COMBO_COLOR: Color = Color::from_rgb(1.0, 0.75, 0.2);
+
 #[derive(GodotClass)]
 #[class(base=CanvasLayer)]
 pub struct Hud {
+    // Nodes of Hud.tscn, resolved and validated in ready().
+    score_label: OnReady<Gd<Label>>,
+    player_score_labels: [OnReady<Gd<Label>>; 2],
+    lives_label: OnReady<Gd<Label>>,
+    message_label: OnReady<Gd<Label>>,
+    message_timer: OnReady<Gd<Timer>>,
+    leaderboard_label: OnReady<Gd<Label>>,
+    power_up_label: OnReady<Gd<Label>>,
+    start_button: OnReady<Gd<Button>>,
+    co_op_button: OnReady<Gd<Button>>,
+    settings_button: OnReady<Gd<Button>>,
+    pause_overlay: OnReady<Gd<ColorRect>>,
+    settings_panel: OnReady<Gd<SettingsPanel>>,
+    achievement_toast: OnReady<Gd<AchievementToast>>,
+    // Whether all of the above could be resolved.
+    nodes_resolved: bool,
+
+    // Last shown values, to render them again when the language changes.
+    scores: Vec<i64>,
+    lives: Vec<i64>,
+    leaderboard: Vec<ScoreEntry>,
+    power_ups: Vec<ActiveEffects>,
+
+    popup_font: OnReady<Gd<Font>>,
     base: Base<CanvasLayer>,
 }
 
 #[godot_api]
 impl Hud {
-    // Public signal, since it's used by Main struct.
+    // Public signal, since it's used by Main struct. `co_op` is true if two players take part.
     #[signal]
-    pub fn start_game();
+    pub fn start_game(co_op: bool);
 
+    // Emitted when the pause action is pressed, both while playing and while paused.
+    #[signal]
+    pub fn pause_toggled();
+
+    /// Shows `text` for a few seconds. Pass a translation key, so that the message follows language changes.
     #[func]
-    pub fn show_message(&self, text: GString) {
-        let mut message_label = self.base().get_node_as::<Label>("MessageLabel");
-        message_label.set_text(&text);
-        message_label.show();
+    pub fn show_message(&mut self, text: GString) {
+        self.message_label.set_text(&text);
+        self.message_label.show();
+
+        self.message_timer.start();
+    }
+
+    pub fn show_game_over(&mut self, new_record: bool) {
+        if new_record {
+            self.show_message("NEW_RECORD".into());
+        } else {
+            self.show_message("GAME_OVER".into());
+        }
+    }
+
+    pub fn show_start_button(&mut self) {
+        self.message_label.set_text("TITLE");
+        self.message_label.show();
+
+        self.start_button.show();
+        self.co_op_button.show();
+        self.settings_button.show();
+        self.leaderboard_label.show();
+
+        // Would overlap the leaderboard.
+        self.lives_label.hide();
+    }
+
+    // No #[func], since Godot has no notion of ScoreEntry.
+    pub fn update_leaderboard(&mut self, entries: &[ScoreEntry]) {
+        self.leaderboard = entries.to_vec();
+        self.render_leaderboard();
+    }
+
+    /// Shows one score per player: a single big one in solo mode, or one in each top corner in co-op.
+    pub fn update_scores(&mut self, scores: &[i64]) {
+        self.scores = scores.to_vec();
+        self.render_scores();
+    }
+
+    /// Shows the lives left, per player in co-op.
+    pub fn update_lives(&mut self, lives: &[i64]) {
+        self.lives = lives.to_vec();
+        self.render_lives();
+        self.lives_label.show();
+    }
+
+    // No #[func], since Godot has no notion of ActiveEffects.
+    // In co-op, each player's effects are prefixed with their number.
+    pub fn update_power_ups(&mut self, per_player: &[ActiveEffects]) {
+        self.power_ups = per_player.to_vec();
+        self.render_power_ups();
+    }
+
+    fn render_leaderboard(&mut self) {
+        let mut text = tr("HIGH_SCORES") + "\n";
+        if self.leaderboard.is_empty() {
+            text += "-";
+        }
+
+        for (i, entry) in self.leaderboard.iter().enumerate() {
+            let seconds = entry.duration as i64;
+            text += &format!(
+                "\n{}. {}  ({}:{:02}, {})",
+                i + 1,
+                entry.score,
+                seconds / 60,
+                seconds % 60,
+                entry.date
+            );
+        }
+
+        self.leaderboard_label.set_text(text.as_str());
+    }
+
+    fn render_scores(&mut self) {
+        let co_op = self.scores.len() > 1;
+
+        self.score_label.set_visible(!co_op);
+        if let [score] = self.scores[..] {
+            self.score_label.set_text(&score.to_string());
+        }
+
+        for (i, label) in self.player_score_labels.iter_mut().enumerate() {
+            label.set_visible(co_op);
+            if let Some(score) = self.scores.get(i) {
+                let text = tr_format("PLAYER_SCORE", &[("player", &(i + 1)), ("score", score)]);
+                label.set_text(text.as_str());
+            }
+        }
+    }
+
+    fn render_lives(&mut self) {
+        let text = match self.lives[..] {
+            [lives] => tr_format("LIVES", &[("lives", &lives)]),
+            _ => self
+                .lives
+                .iter()
+                .enumerate()
+                .map(|(i, lives)| {
+                    tr_format("PLAYER_LIVES", &[("player", &(i + 1)), ("lives", lives)])
+                })
+                .collect::<Vec<_>>()
+                .join("   "),
+        };
 
-        let mut timer = self.base().get_node_as::<Timer>("MessageTimer");
-        timer.start();
+        self.lives_label.set_text(text.as_str());
     }
 
-    pub fn show_game_over(&self) {
-        self.show_message("Game Over".into());
+    fn render_power_ups(&mut self) {
+        let co_op = self.power_ups.len() > 1;
 
-        let mut timer = self.base().get_tree().create_timer(2.0);
-        timer.connect("timeout", &self.base().callable("show_start_button"));
+        let mut text = Vec::new();
+        for (i, effects) in self.power_ups.iter().enumerate() {
+            let mut active = Vec::new();
+            if effects.shield {
+                active.push(tr("POWER_UP_SHIELD"));
+            }
+            if effects.slow_motion > 0.0 {
+                active.push(tr("POWER_UP_SLOW_MOTION"));
+            }
+            if effects.score_multiplier > 0.0 {
+                active.push(tr("POWER_UP_SCORE_MULTIPLIER"));
+            }
+
+            if active.is_empty() {
+                continue;
+            }
+
+            if co_op {
+                let effects = active.join(", ");
+                text.push(tr_format(
+                    "PLAYER_POWER_UPS",
+                    &[("player", &(i + 1)), ("effects", &effects)],
+                ));
+            } else {
+                text.push(active.join("   "));
+            }
+        }
+
+        self.power_up_label.set_text(text.join("   ").as_str());
+    }
+
+    /// Shows the points of a near miss, floating above the player at `position`.
+    pub fn show_near_miss(&mut self, position: Vector2, points: i64, multiplier: i64) {
+        let text = if multiplier > 1 {
+            format!("+{points}  x{multiplier}")
+        } else {
+            format!("+{points}")
+        };
+
+        // Longer combos get warmer colors.
+        let weight = ((multiplier - 1) as f32 / 4.0).clamp(0.0, 1.0);
+        let color = Color::WHITE.lerp(MAX_COMBO_COLOR, weight as f64);
+
+        let mut popup = ScorePopup::new_alloc();
+        popup.set_text(text.as_str());
+        popup.set_horizontal_alignment(HorizontalAlignment::CENTER);
+        popup.add_theme_font_override("font", &*self.popup_font);
+        popup.add_theme_font_size_override("font_size", 28);
+        popup.add_theme_color_override("font_color", color);
+
+        // The HUD keeps processing while the game is paused, but popups should freeze along with the game.
+        popup.set_process_mode(ProcessMode::PAUSABLE);
+
+        self.base_mut().add_child(&popup);
+
+        // Centered, a bit above the player.
+        let size = popup.get_combined_minimum_size();
+        popup.set_position(position - Vector2::new(size.x / 2.0, size.y + 40.0));
+    }
+
+    pub fn show_achievement(&mut self, achievement: &Achievement) {
+        self.achievement_toast.bind_mut().push(achievement);
     }
 
     #[func]
-    fn show_start_button(&mut self) {
-        let mut message_label = self.base().get_node_as::<Label>("MessageLabel");
-        message_label.set_text("Dodge the\nCreeps!");
-        message_label.show();
+    fn on_start_button_pressed(&mut self) {
+        self.hide_start_screen();
 
-        let mut button = self.base().get_node_as::<Button>("StartButton");
-        button.show();
+        self.signals().start_game().emit(false);
     }
 
     #[func]
-    pub fn update_score(&self, score: i64) {
-        let mut label = self.base().get_node_as::<Label>("ScoreLabel");
+    fn on_co_op_button_pressed(&mut self) {
+        self.hide_start_screen();
 
-        label.set_text(&score.to_string());
+        self.signals().start_game().emit(true);
     }
 
     #[func]
-    fn on_start_button_pressed(&mut self) {
-        let mut button = self.base().get_node_as::<Button>("StartButton");
-        button.hide();
+    fn on_settings_button_pressed(&mut self) {
+        // The panel covers the title screen, whose buttons must not react to shortcuts meanwhile.
+        self.hide_start_screen();
+
+        self.settings_panel.bind_mut().open();
+    }
 
-        self.signals().start_game().emit();
+    // No #[func], connected in pure Rust.
+    fn on_settings_closed(&mut self) {
+        self.show_start_button();
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.pause_overlay.set_visible(paused);
+    }
+
+    /// Hides the title screen elements, for games that are started without the start button.
+    pub fn hide_start_screen(&mut self) {
+        self.start_button.hide();
+        self.co_op_button.hide();
+        self.settings_button.hide();
+        self.leaderboard_label.hide();
+    }
+
+    /// Whether all nodes of the HUD were found when it became ready. If not, the game is quitting and the HUD must not be used.
+    pub fn nodes_resolved(&self) -> bool {
+        self.nodes_resolved
     }
 
     #[func]
-    fn on_message_timer_timeout(&self) {
-        let mut message_label = self.base().get_node_as::<Label>("MessageLabel");
-        message_label.hide()
+    fn on_message_timer_timeout(&mut self) {
+        self.message_label.hide()
     }
 }
 
 #[godot_api]
 impl ICanvasLayer for Hud {
     fn init(base: Base<Self::Base>) -> Self {
-        Self { base }
+        Self {
+            // Resolved by hand in ready(), so that all missing nodes are reported at once.
+            score_label: OnReady::manual(),
+            player_score_labels: [OnReady::manual(), OnReady::manual()],
+            lives_label: OnReady::manual(),
+            message_label: OnReady::manual(),
+            message_timer: OnReady::manual(),
+            leaderboard_label: OnReady::manual(),
+            power_up_label: OnReady::manual(),
+            start_button: OnReady::manual(),
+            co_op_button: OnReady::manual(),
+            settings_button: OnReady::manual(),
+            pause_overlay: OnReady::manual(),
+            settings_panel: OnReady::manual(),
+            achievement_toast: OnReady::manual(),
+            nodes_resolved: false,
+            scores: Vec::new(),
+            lives: Vec::new(),
+            leaderboard: Vec::new(),
+            power_ups: Vec::new(),
+            popup_font: OnReady::from_loaded("res://fonts/Xolonium-Regular.ttf"),
+            base,
+        }
+    }
+
+    fn ready(&mut self) {
+        let mut nodes = NodeRefs::new(self.to_gd());
+        let [player1_score_label, player2_score_label] = &mut self.player_score_labels;
+        nodes.resolve(&mut self.score_label, "ScoreLabel");
+        nodes.resolve(player1_score_label, "Player1ScoreLabel");
+        nodes.resolve(player2_score_label, "Player2ScoreLabel");
+        nodes.resolve(&mut self.lives_label, "LivesLabel");
+        nodes.resolve(&mut self.message_label, "MessageLabel");
+        nodes.resolve(&mut self.message_timer, "MessageTimer");
+        nodes.resolve(&mut self.leaderboard_label, "LeaderboardLabel");
+        nodes.resolve(&mut self.power_up_label, "PowerUpLabel");
+        nodes.resolve(&mut self.start_button, "StartButton");
+        nodes.resolve(&mut self.co_op_button, "CoOpButton");
+        nodes.resolve(&mut self.settings_button, "SettingsButton");
+        nodes.resolve(&mut self.pause_overlay, "PauseOverlay");
+        nodes.resolve(&mut self.settings_panel, "SettingsPanel");
+        nodes.resolve(&mut self.achievement_toast, "AchievementToast");
+
+        self.nodes_resolved = nodes.finish();
+        if !self.nodes_resolved {
+            return;
+        }
+
+        // Connect SettingsPanel::closed -> Hud::on_settings_closed.
+        let hud = self.to_gd();
+        self.settings_panel
+            .signals()
+            .closed()
+            .connect_other(&hud, Self::on_settings_closed);
+    }
+
+    // Texts assembled in code are not translated by Godot; see demo_common::locale.
+    fn on_notification(&mut self, what: NodeNotification) {
+        if what == NodeNotification::TRANSLATION_CHANGED && self.nodes_resolved {
+            self.render_scores();
+            self.render_lives();
+            self.render_leaderboard();
+            self.render_power_ups();
+        }
+    }
+
+    // Hud keeps processing while the tree is paused (see Hud.tscn), so it can also receive the action to unpause.
+    fn unhandled_input(&mut self, event: Gd<InputEvent>) {
+        if event.is_action_pressed("pause") {
+            self.base().get_viewport().unwrap().set_input_as_handled();
+
+            self.signals().pause_toggled().emit();
+        }
     }
 }
@@ -0,0 +1,148 @@
+use crate::game_state::GameState;
+use crate::hud::Hud;
+use crate::main_scene::Main;
+use crate::player::Player;
+
+use demo_common::itest::{run_tests, TestCase, TestContext, TestFuture};
+use demo_common::{check, check_eq};
+use godot::classes::Timer;
+use godot::prelude::*;
+
+/// Runs the gameplay tests when `res://itest.tscn` is launched; see `demo_common::itest`.
+#[derive(GodotClass)]
+#[class(init, base=Node)]
+pub struct ItestRunner {
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for ItestRunner {
+    fn ready(&mut self) {
+        run_tests(self.to_gd().upcast(), TESTS);
+    }
+}
+
+const TESTS: &[TestCase] = &[
+    TestCase {
+        name: "new_game_resets_scores",
+        run: new_game_resets_scores,
+    },
+    TestCase {
+        name: "pause_freezes_score",
+        run: pause_freezes_score,
+    },
+    TestCase {
+        name: "player_respawns_until_out_of_lives",
+        run: player_respawns_until_out_of_lives,
+    },
+];
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+// Main scene in which no mobs spawn, so that players are only hit when a test says so.
+// High scores and achievements stay in memory, so that tests don't touch the player's save data.
+fn add_main(ctx: &TestContext) -> Gd<Main> {
+    let main = ctx.add_scene_with::<Main>("res://Main.tscn", |main| {
+        main.set("persist_progress", &false.to_variant());
+    });
+    main.get_node_as::<Timer>("MobTimer").set_paused(true);
+
+    main
+}
+
+fn new_game_resets_scores(ctx: TestContext) -> TestFuture {
+    Box::pin(async move {
+        let main = add_main(&ctx);
+        let hud = main.get_node_as::<Hud>("Hud");
+        check_eq!(main.bind().state(), GameState::Title);
+
+        // Co-op, so that the scores of both players are reset.
+        hud.signals().start_game().emit(true);
+        check_eq!(main.bind().state(), GameState::Countdown);
+
+        ctx.wait_until(8.0, "both players score", || {
+            main.bind().scores().iter().all(|&score| score >= 2)
+        })
+        .await?;
+
+        for name in ["Player", "Player2"] {
+            main.get_node_as::<Player>(name).signals().hit().emit();
+        }
+        check_eq!(main.bind().state(), GameState::GameOver);
+
+        ctx.wait_until(5.0, "title screen after game over", || {
+            main.bind().state() == GameState::Title
+        })
+        .await?;
+
+        hud.signals().start_game().emit(true);
+        check_eq!(main.bind().state(), GameState::Countdown);
+        check_eq!(main.bind().scores(), [0, 0]);
+        // Mobs of the last round are back in the pool.
+        check_eq!(main.bind().mob_pool_stats().active, 0);
+
+        Ok(())
+    })
+}
+
+fn pause_freezes_score(ctx: TestContext) -> TestFuture {
+    Box::pin(async move {
+        let main = add_main(&ctx);
+        let hud = main.get_node_as::<Hud>("Hud");
+
+        hud.signals().start_game().emit(false);
+        ctx.wait_until(5.0, "playing state", || {
+            main.bind().state() == GameState::Playing
+        })
+        .await?;
+
+        hud.signals().pause_toggled().emit();
+        check_eq!(main.bind().state(), GameState::Paused);
+        check!(ctx.tree().is_paused(), "scene tree should be paused");
+
+        let paused_scores = main.bind().scores().to_vec();
+        ctx.seconds(3.0).await;
+        check_eq!(main.bind().scores(), paused_scores);
+
+        hud.signals().pause_toggled().emit();
+        check_eq!(main.bind().state(), GameState::Playing);
+        check!(!ctx.tree().is_paused(), "scene tree should be running");
+
+        Ok(())
+    })
+}
+
+fn player_respawns_until_out_of_lives(ctx: TestContext) -> TestFuture {
+    Box::pin(async move {
+        let main = add_main(&ctx);
+        let hud = main.get_node_as::<Hud>("Hud");
+        let mut player = main.get_node_as::<Player>("Player");
+
+        // In co-op, the round goes on while player 2 is left.
+        hud.signals().start_game().emit(true);
+        ctx.wait_until(5.0, "playing state", || {
+            main.bind().state() == GameState::Playing
+        })
+        .await?;
+
+        let spawn_position = player.get_global_position();
+        let lives = player.bind().lives();
+        check!(lives > 1, "player should start with several lives");
+
+        for lost in 1..lives {
+            player.set_global_position(spawn_position + Vector2::new(100.0, 0.0));
+            player.bind_mut().take_hit();
+
+            check_eq!(player.bind().lives(), lives - lost);
+            check_eq!(player.get_global_position(), spawn_position);
+            check!(player.is_visible(), "player should respawn");
+        }
+
+        player.bind_mut().take_hit();
+        check_eq!(player.bind().lives(), 0);
+        check!(!player.is_visible(), "player should be out of the round");
+        check_eq!(main.bind().state(), GameState::Playing);
+
+        Ok(())
+    })
+}
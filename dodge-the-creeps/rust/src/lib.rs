@@ -7,10 +7,25 @@
 
 use godot::prelude::*;
 
+mod achievements;
+mod camera_effects;
+mod combo;
+mod difficulty;
+mod game_state;
+mod high_scores;
 mod hud;
+mod itest;
 mod main_scene;
 mod mob;
+mod mob_behaviour;
+mod node_pool;
+mod node_refs;
 mod player;
+mod power_up;
+mod replay;
+mod run_rng;
+mod score_popup;
+mod touch_input;
 
 struct DodgeTheCreeps;
 
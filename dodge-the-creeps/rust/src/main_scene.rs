@@ -1,75 +1,340 @@
+use crate::achievements::{CENTURY, DAREDEVIL, GIANT_DODGER, SURVIVOR, UNTOUCHABLE};
+use crate::camera_effects::CameraEffects;
+use crate::combo::MAX_MULTIPLIER;
+use crate::difficulty::DifficultyCurve;
+use crate::game_state::GameState;
+use crate::high_scores::{HighScores, ScoreEntry};
+use crate::mob_behaviour::{MobBehaviour, MobKind, MobTable};
+use crate::node_pool::{NodePool, PoolStats};
+use crate::node_refs::NodeRefs;
+use crate::player::MoveActions;
+use crate::power_up::{ActiveEffects, PowerUp, PowerUpKind};
+use crate::replay::{InputRecording, InputSource, ReplayMode};
+use crate::run_rng::RunRng;
 use crate::{hud, mob, player};
 
-use godot::classes::{AudioStreamPlayer, Marker2D, PathFollow2D, RigidBody2D, Timer};
+use demo_common::achievements::{Achievement, Achievements};
+use demo_common::telemetry::{Event, Telemetry};
+use godot::classes::object::ConnectFlags;
+use godot::classes::{AudioStreamPlayer, Marker2D, Os, PathFollow2D, Time, Timer};
 use godot::prelude::*;
 
 use rand::Rng as _;
-use std::f32::consts::PI;
+use std::f32::consts::{PI, TAU};
 
 // Deriving GodotClass makes the class available to Godot.
 #[derive(GodotClass)]
 #[class(base=Node)]
 pub struct Main {
+    state: GameState,
+    // State to return to when unpausing.
+    state_before_pause: GameState,
+    /// How spawn rate, mob speed and spread evolve with the score.
+    #[export]
+    difficulty: OnEditor<Gd<DifficultyCurve>>,
+    /// How often each kind of mob behaviour is spawned.
+    #[export]
+    mob_table: OnEditor<Gd<MobTable>>,
+    /// Seed for all spawning randomness. 0 picks a new random seed for every run.
+    #[export]
+    seed: i64,
+    rng: RunRng,
+    /// Whether runs are recorded to, or played back from, `replay_path`.
+    #[export]
+    replay_mode: ReplayMode,
+    #[export]
+    replay_path: GString,
+    // Set when a replay was requested on the command line; the game then quits once the replay ends.
+    quit_after_replay: bool,
+    /// Whether gameplay events are logged to `user://dodge_telemetry.jsonl`, for analysing game balance. Can also be turned on with
+    /// `--telemetry` on the command line.
+    #[export]
+    record_telemetry: bool,
+    telemetry: Telemetry,
+    /// Whether high scores and unlocked achievements are saved to `user://`. The gameplay tests turn this off, to leave the player's
+    /// progress alone.
+    #[export]
+    persist_progress: bool,
     mob_scene: OnReady<Gd<PackedScene>>,
-    player: OnReady<Gd<player::Player>>,
+    /// Lives each player starts a round with.
+    #[export]
+    lives: i64,
+    /// Number of mobs instantiated up front, before the first round.
+    #[export]
+    mob_pool_size: i64,
+    mob_pool: OnReady<NodePool<mob::Mob>>,
+    /// Points between two boss waves. 0 disables boss waves.
+    #[export]
+    boss_interval: i64,
+    /// Screen shake (between 0 and 1) when a player loses a life.
+    #[export]
+    hit_trauma: f32,
+    /// Seconds of hit-stop when a player loses a life.
+    #[export]
+    hit_stop_time: f64,
+    /// Screen flash when a player loses a life; alpha is the initial opacity.
+    #[export]
+    hit_flash_color: Color,
+    /// Screen shake for every near miss.
+    #[export]
+    near_miss_trauma: f32,
+    /// Screen flash when a near-miss combo reaches the highest multiplier.
+    #[export]
+    max_combo_flash_color: Color,
+    /// Screen shake when a boss wave begins.
+    #[export]
+    boss_trauma: f32,
+    // Score at which the next boss wave starts.
+    next_boss_score: i64,
+    // The boss of the current wave; normal spawning is paused while it is set.
+    boss: Option<Gd<mob::Mob>>,
+    power_up_scene: OnReady<Gd<PackedScene>>,
+    // Player 2 only takes part in co-op rounds.
+    players: [OnReady<Gd<player::Player>>; 2],
+    // Number of players in the current round: 1, or 2 in co-op.
+    player_count: usize,
+    // Nodes of Main.tscn, resolved and validated in ready().
     hud: OnReady<Gd<hud::Hud>>,
     music: OnReady<Gd<AudioStreamPlayer>>,
     death_sound: OnReady<Gd<AudioStreamPlayer>>,
-    score: i64,
+    camera: OnReady<Gd<CameraEffects>>,
+    start_timer: OnReady<Gd<Timer>>,
+    score_timer: OnReady<Gd<Timer>>,
+    mob_timer: OnReady<Gd<Timer>>,
+    power_up_timer: OnReady<Gd<Timer>>,
+    start_position: OnReady<Gd<Marker2D>>,
+    mob_spawn_location: OnReady<Gd<PathFollow2D>>,
+    high_scores: OnReady<HighScores>,
+    achievements: OnReady<Achievements>,
+    // Per player; entries beyond `player_count` are unused.
+    scores: [i64; 2],
+    alive: [bool; 2],
+    run_start_msec: u64,
+    // Current factor applied to all mob velocities (slow-motion power-up).
+    mob_speed_scale: real,
     base: Base<Node>,
 }
 
 #[godot_api]
 impl INode for Main {
     fn init(base: Base<Node>) -> Self {
-        // We could also initialize those manually inside ready(), but OnReady automatically defers initialization.
+        // OnReady defers initialization until ready(), either automatically or by hand (see OnReady::manual()).
         // Alternatively to init(), you can use #[init(...)] on the struct fields.
         Self {
+            state: GameState::Title,
+            state_before_pause: GameState::Title,
+            // Set in the Inspector; the scene fails to start if it's missing.
+            difficulty: OnEditor::default(),
+            mob_table: OnEditor::default(),
+            seed: 0,
+            // Re-created with the actual seed at the start of every run.
+            rng: RunRng::new(0),
+            replay_mode: ReplayMode::Off,
+            replay_path: "user://last_run.replay".into(),
+            quit_after_replay: false,
+            record_telemetry: false,
+            // Started in ready(), once the exported setting is known.
+            telemetry: Telemetry::disabled(),
+            persist_progress: true,
             // OnReady::from_loaded(path) == OnReady::new(|| tools::load(path)).
             mob_scene: OnReady::from_loaded("res://Mob.tscn"),
-            player: OnReady::from_node("Player"),
-            hud: OnReady::from_node("Hud"),
-            music: OnReady::from_node("Music"),
-            death_sound: OnReady::from_node("DeathSound"),
-            score: 0,
+            lives: 3,
+            mob_pool_size: 64,
+            // Depends on exported fields and the Gd<Main> pointer, so it's initialized by hand in ready().
+            mob_pool: OnReady::manual(),
+            boss_interval: 30,
+            hit_trauma: 0.6,
+            hit_stop_time: 0.12,
+            hit_flash_color: Color::from_rgba(1.0, 0.2, 0.2, 0.4),
+            near_miss_trauma: 0.15,
+            max_combo_flash_color: Color::from_rgba(1.0, 0.85, 0.3, 0.25),
+            boss_trauma: 0.5,
+            next_boss_score: 0,
+            boss: None,
+            power_up_scene: OnReady::from_loaded("res://PowerUp.tscn"),
+            // Child nodes are resolved by hand in ready(), so that all missing ones are reported at once.
+            players: [OnReady::manual(), OnReady::manual()],
+            player_count: 1,
+            hud: OnReady::manual(),
+            music: OnReady::manual(),
+            death_sound: OnReady::manual(),
+            camera: OnReady::manual(),
+            start_timer: OnReady::manual(),
+            score_timer: OnReady::manual(),
+            mob_timer: OnReady::manual(),
+            power_up_timer: OnReady::manual(),
+            start_position: OnReady::manual(),
+            mob_spawn_location: OnReady::manual(),
+            // Loaded in ready(), depending on `persist_progress`.
+            high_scores: OnReady::manual(),
+            achievements: OnReady::manual(),
+            scores: [0; 2],
+            alive: [false; 2],
+            run_start_msec: 0,
+            mob_speed_scale: 1.0,
             base,
         }
     }
 
     fn ready(&mut self) {
-        // The OnReady instances are now initialized, we can access them like normal fields.
+        // Save files are only read once the scene is ready, since `persist_progress` may be set after init().
+        let (high_scores, achievements) = if self.persist_progress {
+            (
+                HighScores::load(),
+                Achievements::load("dodge_achievements.cfg"),
+            )
+        } else {
+            (HighScores::in_memory(), Achievements::in_memory())
+        };
+        self.high_scores.init(high_scores);
+        self.achievements.init(achievements);
+
+        // The automatic OnReady instances are now initialized; the manual ones for child nodes follow here.
+        // A scene with missing or mistyped nodes is reported once, with all problems, instead of panicking mid-game.
+        let mut nodes = NodeRefs::new(self.to_gd());
+        let [player1, player2] = &mut self.players;
+        nodes.resolve(player1, "Player");
+        nodes.resolve(player2, "Player2");
+        nodes.resolve(&mut self.hud, "Hud");
+        nodes.resolve(&mut self.music, "Music");
+        nodes.resolve(&mut self.death_sound, "DeathSound");
+        nodes.resolve(&mut self.camera, "CameraEffects");
+        nodes.resolve(&mut self.start_timer, "StartTimer");
+        nodes.resolve(&mut self.score_timer, "ScoreTimer");
+        nodes.resolve(&mut self.mob_timer, "MobTimer");
+        nodes.resolve(&mut self.power_up_timer, "PowerUpTimer");
+        nodes.resolve(&mut self.start_position, "StartPosition");
+        nodes.resolve(&mut self.mob_spawn_location, "MobPath/MobSpawnLocation");
+
+        // The HUD validated its own nodes before (children are ready first), and has reported them already.
+        if !nodes.finish() || !self.hud.bind().nodes_resolved() {
+            return;
+        }
 
         // Get a Gd<Main> pointer to this instance.
         let main = self.to_gd();
 
-        // Connect Player::hit -> Main::game_over.
-        self.player
-            .signals()
-            .hit()
-            .connect_other(&main, Self::game_over);
+        if self.record_telemetry || Telemetry::requested_on_command_line() {
+            self.telemetry = Telemetry::start("dodge_telemetry.jsonl");
+        }
 
-        // Connect Hud::start_game -> Main::new_game.
+        // Every mob is connected once when created; recycled mobs keep their connections.
+        // Deferred, because mobs may ask to despawn or split during physics callbacks, where the tree cannot be changed.
+        let main_for_pool = main.clone();
+        let mob_pool = NodePool::new(
+            self.mob_scene.clone(),
+            self.mob_pool_size.max(0) as usize,
+            move |mob: &Gd<mob::Mob>| {
+                let recycled = mob.clone();
+                mob.signals()
+                    .despawn_requested()
+                    .builder()
+                    .flags(ConnectFlags::DEFERRED)
+                    .connect_other_mut(&main_for_pool, move |this: &mut Self| {
+                        this.on_mob_despawned(&recycled);
+                    });
+
+                mob.signals()
+                    .split_requested()
+                    .builder()
+                    .flags(ConnectFlags::DEFERRED)
+                    .connect_other_mut(&main_for_pool, Self::on_mob_split_requested);
+
+                mob.signals()
+                    .ring_fired()
+                    .builder()
+                    .flags(ConnectFlags::DEFERRED)
+                    .connect_other_mut(&main_for_pool, Self::on_mob_ring_fired);
+            },
+        );
+        self.mob_pool.init(mob_pool);
+
+        for (index, player) in self.players.iter().enumerate() {
+            // Connect Player::hit -> Main::on_player_hit, telling which player was hit.
+            player
+                .signals()
+                .hit()
+                .connect_other(&main, move |this: &mut Self| this.on_player_hit(index));
+
+            // Connect Player::near_miss -> Main::on_player_near_miss, telling which player dodged.
+            player.signals().near_miss().connect_other(
+                &main,
+                move |this: &mut Self, points: i64, multiplier: i64, position: Vector2| {
+                    this.on_player_near_miss(index, points, multiplier, position)
+                },
+            );
+
+            // Connect Player::effects_changed -> Main::on_player_effects_changed.
+            player
+                .signals()
+                .effects_changed()
+                .connect_other(&main, Self::on_player_effects_changed);
+
+            // Connect Player::lives_changed -> Main::on_player_lost_life, telling which player lost it.
+            player
+                .signals()
+                .lives_changed()
+                .connect_other(&main, move |this: &mut Self| {
+                    this.on_player_lost_life(index)
+                });
+        }
+
+        // Connect Hud::start_game -> Main::on_hud_start_game.
         self.hud
             .signals()
             .start_game()
-            .connect_other(&main, Self::new_game);
+            .connect_other(&main, Self::on_hud_start_game);
+
+        // Connect Hud::pause_toggled -> Main::on_hud_pause_toggled.
+        self.hud
+            .signals()
+            .pause_toggled()
+            .connect_other(&main, Self::on_hud_pause_toggled);
 
         // Connect Main.ScoreTimer::timeout -> Main::on_score_timer_timeout.
-        self.score_timer()
+        self.score_timer
             .signals()
             .timeout()
             .connect_other(&main, Self::on_score_timer_timeout);
 
         // Connect Main.MobTimer::timeout -> Main::on_mob_timer_timeout.
-        self.mob_timer()
+        self.mob_timer
             .signals()
             .timeout()
             .connect_other(&main, Self::on_mob_timer_timeout);
 
+        // Connect Main.PowerUpTimer::timeout -> Main::on_power_up_timer_timeout.
+        self.power_up_timer
+            .signals()
+            .timeout()
+            .connect_other(&main, Self::on_power_up_timer_timeout);
+
+        self.hud
+            .bind()
+            .update_leaderboard(self.high_scores.entries());
+
+        // Replays can be run without the editor, e.g. headless for regression tests:
+        //   godot --headless --path dodge-the-creeps/godot -- --replay=user://last_run.replay
+        let user_args = Os::singleton().get_cmdline_user_args();
+        let replay_arg = user_args
+            .as_slice()
+            .iter()
+            .find_map(|arg| arg.to_string().strip_prefix("--replay=").map(GString::from));
+
+        if let Some(path) = replay_arg {
+            self.replay_mode = ReplayMode::Play;
+            self.replay_path = path;
+            self.quit_after_replay = true;
+            self.player_count = 1;
+
+            self.hud.bind_mut().hide_start_screen();
+            self.set_state(GameState::Countdown);
+        }
+
         // Main.StartTimer::timeout -> Main::on_start_timer_timeout is set up in the Editor's Inspector UI, but could be done here as well,
         // as follows. Note that signal handlers connected via Rust do not need a #[func] annotation, they can remain entirely visible to Godot.
         //
-        // self.start_timer()
+        // self.start_timer
         //     .signals()
         //     .timeout()
         //     .connect_other(&main, Self::on_start_timer_timeout);
@@ -78,87 +343,581 @@ impl INode for Main {
 
 #[godot_api]
 impl Main {
+    /// Moves the game to `next`, running the entry actions of that state.
+    ///
+    /// All timer starts/stops and HUD screen changes happen here, so that each state has a single well-defined setup.
+    fn set_state(&mut self, next: GameState) {
+        let previous = self.state;
+        if !previous.can_transition_to(next) {
+            godot_warn!("Ignoring invalid game state transition {previous:?} -> {next:?}.");
+            return;
+        }
+
+        self.state = next;
+
+        match (previous, next) {
+            (_, GameState::Paused) => self.pause(previous),
+            (GameState::Paused, _) => self.resume(),
+            (_, GameState::Title) => self.hud.bind_mut().show_start_button(),
+            (_, GameState::Countdown) => self.new_game(),
+            (_, GameState::Playing) => self.start_playing(),
+            (_, GameState::GameOver) => self.game_over(),
+        }
+    }
+
     // No #[func] here, this method is directly called from Rust (via type-safe signals).
+    fn on_player_hit(&mut self, index: usize) {
+        // Hits on a player who is not (or no longer) in the round don't count.
+        if index >= self.player_count || !self.alive[index] {
+            return;
+        }
+
+        self.alive[index] = false;
+
+        // The round goes on as long as one player is left.
+        if !self.alive.contains(&true) {
+            self.set_state(GameState::GameOver);
+        }
+    }
+
+    // No #[func], connected in pure Rust.
+    fn on_hud_start_game(&mut self, co_op: bool) {
+        self.player_count = if co_op { 2 } else { 1 };
+        self.set_state(GameState::Countdown);
+    }
+
+    // No #[func], connected in pure Rust.
+    fn on_hud_pause_toggled(&mut self) {
+        if self.state == GameState::Paused {
+            self.set_state(self.state_before_pause);
+        } else if self.state.is_pausable() {
+            self.set_state(GameState::Paused);
+        }
+    }
+
+    #[func] // needed because connected in Editor UI (see ready).
+    fn on_start_timer_timeout(&mut self) {
+        self.set_state(GameState::Playing);
+    }
+
+    // Entry action of GameState::Paused.
+    fn pause(&mut self, paused_from: GameState) {
+        self.state_before_pause = paused_from;
+
+        // Freezes all nodes with the default process mode, including timers, mobs and the player.
+        // Hud is set to "Always" in the scene, so that it can still show the overlay and receive the pause action.
+        self.base().get_tree().set_pause(true);
+        self.hud.bind_mut().set_paused(true);
+    }
+
+    // Exit action of GameState::Paused.
+    fn resume(&mut self) {
+        self.base().get_tree().set_pause(false);
+        self.hud.bind_mut().set_paused(false);
+    }
+
+    // Entry action of GameState::GameOver.
     fn game_over(&mut self) {
-        self.score_timer().stop();
-        self.mob_timer().stop();
+        self.start_timer.stop();
+        self.score_timer.stop();
+        self.mob_timer.stop();
+        self.power_up_timer.stop();
+
+        if let Some(recording) = self.players[0].bind_mut().take_recording() {
+            recording.save(&self.replay_path);
+        }
+
+        let elapsed_msec = Time::singleton()
+            .get_ticks_msec()
+            .saturating_sub(self.run_start_msec);
+        let duration = elapsed_msec as f64 / 1000.0;
+
+        self.telemetry.log(
+            Event::new("run_end")
+                .with_int("score", self.score())
+                .with_float("duration", duration),
+        );
+
+        if self.quit_after_replay {
+            godot_print!("Replay finished with score {}", self.score());
+            self.base().get_tree().quit();
+            return;
+        }
+
+        // Replays and co-op rounds don't count towards the high scores, which compare solo runs.
+        let rank = if self.replay_mode == ReplayMode::Play || self.player_count > 1 {
+            None
+        } else {
+            let entry = ScoreEntry::new(self.score(), duration);
+            self.high_scores.submit(entry)
+        };
 
-        self.hud.bind_mut().show_game_over();
+        let mut hud = self.hud.bind_mut();
+        hud.update_leaderboard(self.high_scores.entries());
+        hud.show_game_over(rank == Some(0));
+        drop(hud);
 
         self.music.stop();
         self.death_sound.play();
+
+        // Back to the title screen once the game over message has been shown for a while.
+        let main = self.to_gd();
+        self.base()
+            .get_tree()
+            .create_timer(2.0)
+            .signals()
+            .timeout()
+            .builder()
+            .connect_other_mut(&main, |this: &mut Self| {
+                this.set_state(GameState::Title);
+            });
     }
 
-    // No #[func].
-    pub fn new_game(&mut self) {
-        let start_position = self.base().get_node_as::<Marker2D>("StartPosition");
+    // Entry action of GameState::Countdown.
+    fn new_game(&mut self) {
+        // In co-op, players start side by side, this far apart.
+        const CO_OP_SPACING: real = 120.0;
+
+        let start_position = self.start_position.get_position();
+        let co_op = self.player_count > 1;
+
+        self.scores = [0; 2];
+        self.alive = [false; 2];
+        // Otherwise, the first spawn would still use the interval of the last round's final score.
+        let spawn_interval = self.difficulty.bind().sample(0).spawn_interval;
+        self.mob_timer.set_wait_time(spawn_interval);
+        self.next_boss_score = self.boss_interval;
+        // The boss of the last round (if any) is cleared away with the other mobs below.
+        self.boss = None;
+
+        // Recordings hold the input of a single player.
+        let replay_mode = if co_op {
+            if self.replay_mode != ReplayMode::Off {
+                godot_warn!("Replays are only supported in solo mode; co-op rounds are neither recorded nor replayed.");
+            }
+            ReplayMode::Off
+        } else {
+            self.replay_mode
+        };
+
+        // A replay brings its own seed, so that mobs spawn exactly as in the recorded run.
+        let replay = match replay_mode {
+            ReplayMode::Play => InputRecording::load(&self.replay_path),
+            _ => None,
+        };
+        let seed = replay.as_ref().map_or(self.seed, InputRecording::seed);
+
+        self.rng = RunRng::new(seed);
+        godot_print!("Starting run with seed {}", self.rng.seed());
+
+        self.telemetry.log(
+            Event::new("run_start")
+                .with_int("seed", self.rng.seed())
+                .with_bool("co_op", co_op)
+                .with_bool("replay", replay_mode == ReplayMode::Play),
+        );
+
+        let input = match (replay_mode, replay) {
+            (ReplayMode::Play, Some(recording)) => InputSource::replay(recording),
+            (ReplayMode::Record, _) => InputSource::Recording(InputRecording::new(self.rng.seed())),
+            _ => InputSource::Live,
+        };
+        self.players[0].bind_mut().set_input_source(input);
+        self.players[1]
+            .bind_mut()
+            .set_input_source(InputSource::Live);
+
+        // Solo mode accepts all keys and gamepads; in co-op, each player has their own half of the keyboard and their own gamepad.
+        for index in 0..self.player_count {
+            let (actions, position) = if co_op {
+                let offset = (index as real - 0.5) * CO_OP_SPACING;
+                let actions = MoveActions::with_prefix(&format!("p{}_", index + 1));
+
+                (actions, start_position + Vector2::new(offset, 0.0))
+            } else {
+                (MoveActions::with_prefix(""), start_position)
+            };
+
+            self.alive[index] = true;
+
+            let mut player = self.players[index].bind_mut();
+            player.set_move_actions(actions);
+            player.start(position, self.lives);
+        }
 
-        self.score = 0;
+        self.on_player_effects_changed();
+        self.on_player_lives_changed();
+        self.start_timer.start();
 
-        self.player.bind_mut().start(start_position.get_position());
-        self.start_timer().start();
+        // Mobs and pickups from the last round are cleared away.
+        self.mob_pool.release_all();
+        self.base()
+            .get_tree()
+            .call_group("power_ups", "queue_free", &[]);
 
-        let hud = self.hud.bind_mut();
-        hud.update_score(self.score);
-        hud.show_message("Get Ready".into());
+        let mut hud = self.hud.bind_mut();
+        hud.update_scores(&self.scores[..self.player_count]);
+        hud.show_message("GET_READY".into());
 
         self.music.play();
     }
 
-    #[func] // needed because connected in Editor UI (see ready).
-    fn on_start_timer_timeout(&mut self) {
-        self.mob_timer().start();
-        self.score_timer().start();
+    // Entry action of GameState::Playing.
+    fn start_playing(&mut self) {
+        self.run_start_msec = Time::singleton().get_ticks_msec();
+
+        self.mob_timer.start();
+        self.score_timer.start();
+        self.power_up_timer.start();
     }
 
     // No #[func], connected in pure Rust.
     fn on_score_timer_timeout(&mut self) {
-        self.score += 1;
+        // Players who are down keep the score they had.
+        for index in 0..self.player_count {
+            if self.alive[index] {
+                self.scores[index] += self.players[index].bind().effects().score_multiplier();
+
+                self.telemetry.log(
+                    Event::new("score")
+                        .with_int("player", index as i64)
+                        .with_int("score", self.scores[index]),
+                );
+            }
+        }
+
+        self.hud
+            .bind_mut()
+            .update_scores(&self.scores[..self.player_count]);
+
+        let elapsed_msec = Time::singleton()
+            .get_ticks_msec()
+            .saturating_sub(self.run_start_msec);
+        if elapsed_msec >= 60_000 {
+            self.unlock(&SURVIVOR);
+        }
+        if self.score() >= 100 {
+            self.unlock(&CENTURY);
+        }
+        for index in 0..self.player_count {
+            let unhurt = self.players[index].bind().lives() == self.lives;
+            if self.alive[index] && unhurt && self.scores[index] >= 50 {
+                self.unlock(&UNTOUCHABLE);
+            }
+        }
+
+        self.check_boss_wave();
+    }
+
+    // No #[func], connected in pure Rust.
+    fn on_player_near_miss(
+        &mut self,
+        index: usize,
+        points: i64,
+        multiplier: i64,
+        position: Vector2,
+    ) {
+        // Mobs keep flying after the round, but only count while it's on.
+        if self.state != GameState::Playing || !self.alive[index] {
+            return;
+        }
+
+        self.scores[index] += points;
+
+        self.telemetry.log(
+            Event::new("near_miss")
+                .with_int("player", index as i64)
+                .with_int("points", points)
+                .with_int("multiplier", multiplier),
+        );
+
+        let mut hud = self.hud.bind_mut();
+        hud.update_scores(&self.scores[..self.player_count]);
+        hud.show_near_miss(position, points, multiplier);
+        drop(hud);
+
+        self.camera.bind_mut().add_trauma(self.near_miss_trauma);
+        if multiplier >= MAX_MULTIPLIER {
+            self.camera.bind_mut().flash(self.max_combo_flash_color);
+            self.unlock(&DAREDEVIL);
+        }
+
+        self.check_boss_wave();
+    }
+
+    // Starts a boss wave once the score reaches the next threshold.
+    fn check_boss_wave(&mut self) {
+        const BOSS_SPEED: real = 55.0;
+        const BOSS_SIZE: real = 2.5;
+
+        if self.boss_interval <= 0
+            || self.boss.is_some()
+            || self.state != GameState::Playing
+            || self.score() < self.next_boss_score
+        {
+            return;
+        }
+
+        // Normal spawning pauses until the boss has left the screen.
+        self.mob_timer.stop();
+        self.hud.bind_mut().show_message("BOSS_INCOMING".into());
+        self.camera.bind_mut().add_trauma(self.boss_trauma);
+
+        let progress = self.rng.gen_range(u32::MIN..u32::MAX);
+        let behaviour = MobKind::Boss.create_behaviour(&mut self.rng);
+
+        let mut mob_spawn_location = self.mob_spawn_location.clone();
+        mob_spawn_location.set_progress(progress as f32);
+
+        // Straight through the middle of the screen, slowly.
+        let position = mob_spawn_location.get_position();
+        let center = self.base().get_viewport().unwrap().get_visible_rect().size / 2.0;
+        let velocity = (center - position).normalized() * BOSS_SPEED;
+
+        self.telemetry
+            .log(Event::new("boss_wave").with_int("score", self.score()));
+
+        let boss = self.spawn_mob(position, velocity, MobKind::Boss, behaviour, BOSS_SIZE);
+        self.boss = Some(boss);
+    }
+
+    // Called (deferred) when a mob left the screen, or was popped by a shield.
+    fn on_mob_despawned(&mut self, mob: &Gd<mob::Mob>) {
+        self.mob_pool.release(mob);
+
+        if self.boss.as_ref() != Some(mob) {
+            return;
+        }
+
+        self.boss = None;
+        self.next_boss_score = self.score() + self.boss_interval;
+
+        // A boss that leaves after the round ended must not restart spawning.
+        if self.state == GameState::Playing || self.state == GameState::Paused {
+            self.mob_timer.start();
+            self.unlock(&GIANT_DODGER);
+        }
+    }
 
-        self.hud.bind_mut().update_score(self.score);
+    // No #[func], connected in pure Rust.
+    fn on_mob_ring_fired(&mut self, position: Vector2, bullets: u32, offset: real) {
+        const BULLET_SPEED: real = 150.0;
+        const BULLET_SIZE: real = 0.4;
+
+        // Rings fired after the round ended would leave stray bullets on the title screen.
+        if self.state != GameState::Playing && self.state != GameState::Paused {
+            return;
+        }
+
+        for i in 0..bullets {
+            let angle = offset + TAU * i as real / bullets as real;
+            let velocity = Vector2::RIGHT.rotated(angle) * BULLET_SPEED;
+            let behaviour = MobKind::Straight.create_behaviour(&mut self.rng);
+
+            self.spawn_mob(
+                position,
+                velocity,
+                MobKind::Straight,
+                behaviour,
+                BULLET_SIZE,
+            );
+        }
     }
 
     // No #[func], connected in pure Rust.
     fn on_mob_timer_timeout(&mut self) {
-        let mut mob_spawn_location = self
-            .base()
-            .get_node_as::<PathFollow2D>("MobPath/MobSpawnLocation");
+        let difficulty = self.difficulty.bind().sample(self.score());
+
+        // The new interval takes effect once the timer restarts, i.e. from the next spawn on.
+        self.mob_timer.set_wait_time(difficulty.spawn_interval);
 
-        let mut mob_scene = self.mob_scene.instantiate_as::<RigidBody2D>();
+        // Draw all random values up front: `self.rng` cannot be borrowed while `self.base_mut()` is active.
+        let progress = self.rng.gen_range(u32::MIN..u32::MAX);
+        let deviation = self.rng.gen_range(-difficulty.spread..=difficulty.spread);
+        let speed = self
+            .rng
+            .gen_range(difficulty.min_speed..=difficulty.max_speed);
+        let kind = self.mob_table.bind().pick(&mut self.rng);
+        let behaviour = kind.create_behaviour(&mut self.rng);
 
-        let mut rng = rand::thread_rng();
-        let progress = rng.gen_range(u32::MIN..u32::MAX);
+        let mut mob_spawn_location = self.mob_spawn_location.clone();
 
         mob_spawn_location.set_progress(progress as f32);
-        mob_scene.set_position(mob_spawn_location.get_position());
 
-        let mut direction = mob_spawn_location.get_rotation() + PI / 2.0;
-        direction += rng.gen_range(-PI / 4.0..PI / 4.0);
+        let direction = mob_spawn_location.get_rotation() + PI / 2.0 + deviation;
+        let velocity = Vector2::new(speed, 0.0).rotated(real::from_f32(direction));
+
+        self.spawn_mob(
+            mob_spawn_location.get_position(),
+            velocity,
+            kind,
+            behaviour,
+            1.0,
+        );
+    }
+
+    // No #[func], connected in pure Rust.
+    fn on_mob_split_requested(&mut self, position: Vector2, velocity: Vector2) {
+        // Fragments fan out from the splitter's heading, and are a bit faster than it.
+        const FRAGMENT_ANGLES: [real; 3] = [-0.5, 0.0, 0.5];
+        const FRAGMENT_SIZE: real = 0.55;
+
+        // A split that arrives after the round ended would leave stray fragments on the title screen.
+        if self.state != GameState::Playing && self.state != GameState::Paused {
+            return;
+        }
 
-        mob_scene.set_rotation(direction);
+        for angle in FRAGMENT_ANGLES {
+            let behaviour = MobKind::Straight.create_behaviour(&mut self.rng);
+            let fragment_velocity = velocity.rotated(angle) * 1.2;
+
+            self.spawn_mob(
+                position,
+                fragment_velocity,
+                MobKind::Straight,
+                behaviour,
+                FRAGMENT_SIZE,
+            );
+        }
+    }
 
-        self.base_mut().add_child(&mob_scene);
+    fn spawn_mob(
+        &mut self,
+        position: Vector2,
+        velocity: Vector2,
+        kind: MobKind,
+        behaviour: Box<dyn MobBehaviour>,
+        size: real,
+    ) -> Gd<mob::Mob> {
+        self.telemetry.log(
+            Event::new("spawn")
+                .with_str("kind", &format!("{kind:?}"))
+                .with_float("x", position.x as f64)
+                .with_float("y", position.y as f64)
+                .with_float("speed", velocity.length() as f64)
+                .with_float("size", size as f64),
+        );
+
+        let mut mob = self.mob_pool.acquire();
+        mob.set_position(position);
+
+        self.base_mut().add_child(&mob);
+
+        {
+            let mut mob = mob.bind_mut();
+            mob.set_speed_scale(self.mob_speed_scale);
+            mob.initialize(&mut self.rng, behaviour, velocity, size);
+        }
 
-        let mut mob = mob_scene.cast::<mob::Mob>();
-        let range = {
-            // Local scope to bind `mob` user object
-            let mob = mob.bind();
-            rng.gen_range(mob.min_speed..mob.max_speed)
-        };
+        mob
+    }
+
+    // No #[func], connected in pure Rust.
+    fn on_power_up_timer_timeout(&mut self) {
+        // Keep pickups away from the screen edges, where they would be hard to reach.
+        const MARGIN: real = 40.0;
+
+        let screen_size = self.base().get_viewport().unwrap().get_visible_rect().size;
+        let kind = PowerUpKind::ALL[self.rng.gen_range(0..PowerUpKind::ALL.len())];
+        let position = Vector2::new(
+            self.rng.gen_range(MARGIN..screen_size.x - MARGIN),
+            self.rng.gen_range(MARGIN..screen_size.y - MARGIN),
+        );
+
+        let mut power_up = self.power_up_scene.instantiate_as::<PowerUp>();
+        power_up.bind_mut().set_kind(kind);
+        power_up.set_position(position);
+
+        self.base_mut().add_child(&power_up);
+    }
+
+    // Called whenever a player's power-ups change, and once at the start of a round.
+    fn on_player_effects_changed(&mut self) {
+        let effects: Vec<ActiveEffects> = self.players[..self.player_count]
+            .iter()
+            .map(|player| *player.bind().effects())
+            .collect();
+
+        self.hud.bind_mut().update_power_ups(&effects);
+
+        // Slow motion collected by any player slows down the mobs for everyone.
+        // Rescale mobs that are already moving; new mobs pick up the scale when spawned.
+        let new_scale = effects
+            .iter()
+            .map(ActiveEffects::mob_speed_scale)
+            .fold(1.0, real::min);
+        if new_scale != self.mob_speed_scale {
+            self.mob_speed_scale = new_scale;
+
+            let mobs = self.base().get_tree().get_nodes_in_group("mobs");
+            for mob in mobs.iter_shared() {
+                if let Ok(mut mob) = mob.try_cast::<mob::Mob>() {
+                    mob.bind_mut().set_speed_scale(new_scale);
+                }
+            }
+        }
+    }
+
+    // No #[func], connected in pure Rust.
+    fn on_player_lost_life(&mut self, index: usize) {
+        let lives = self.players[index].bind().lives();
+        self.telemetry.log(
+            Event::new("hit")
+                .with_int("player", index as i64)
+                .with_int("lives", lives),
+        );
+
+        let mut camera = self.camera.bind_mut();
+        camera.add_trauma(self.hit_trauma);
+        camera.hit_stop(self.hit_stop_time);
+        camera.flash(self.hit_flash_color);
+        drop(camera);
+
+        self.on_player_lives_changed();
+    }
+
+    // Shows the lives of all players in the round.
+    fn on_player_lives_changed(&mut self) {
+        let lives: Vec<i64> = self.players[..self.player_count]
+            .iter()
+            .map(|player| player.bind().lives())
+            .collect();
+
+        self.hud.bind_mut().update_lives(&lives);
+    }
+
+    // Celebrates an achievement the first time it is reached. Replays only re-enact a run, so they don't count.
+    fn unlock(&mut self, achievement: &Achievement) {
+        if self.replay_mode == ReplayMode::Play || !self.achievements.unlock(achievement) {
+            return;
+        }
+
+        self.telemetry
+            .log(Event::new("achievement").with_str("id", achievement.id));
+        self.hud.bind_mut().show_achievement(achievement);
+    }
 
-        mob.set_linear_velocity(Vector2::new(range, 0.0).rotated(real::from_f32(direction)));
+    // Inspected by the gameplay tests.
+    pub(crate) fn state(&self) -> GameState {
+        self.state
     }
 
-    // These timers could also be stored as OnReady fields, but are now fetched via function for demonstration purposes.
-    fn start_timer(&self) -> Gd<Timer> {
-        self.base().get_node_as::<Timer>("StartTimer")
+    // Inspected by the gameplay tests.
+    pub(crate) fn mob_pool_stats(&self) -> PoolStats {
+        self.mob_pool.stats()
     }
 
-    fn score_timer(&self) -> Gd<Timer> {
-        self.base().get_node_as::<Timer>("ScoreTimer")
+    // Inspected by the gameplay tests. One entry per player in the round.
+    pub(crate) fn scores(&self) -> &[i64] {
+        &self.scores[..self.player_count]
     }
 
-    fn mob_timer(&self) -> Gd<Timer> {
-        self.base().get_node_as::<Timer>("MobTimer")
+    /// Best score among the players in the round; this is what drives the difficulty.
+    fn score(&self) -> i64 {
+        self.scores[..self.player_count]
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0)
     }
 }
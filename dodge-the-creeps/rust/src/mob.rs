@@ -1,25 +1,51 @@
-use godot::classes::{AnimatedSprite2D, IRigidBody2D, RigidBody2D};
+use crate::mob_behaviour::{MobAction, MobBehaviour, MobContext, Straight};
+use crate::node_refs::NodeRefs;
+
+use godot::classes::{
+    AnimatedSprite2D, CollisionShape2D, IRigidBody2D, RigidBody2D, VisibleOnScreenNotifier2D,
+};
 use godot::prelude::*;
 
+use rand::Rng;
+
 #[derive(GodotClass)]
 #[class(base=RigidBody2D)]
 pub struct Mob {
-    pub min_speed: real,
-    pub max_speed: real,
-
+    behaviour: Box<dyn MobBehaviour>,
+    // Velocity as decided by the behaviour; the body moves at this times `speed_scale`.
+    velocity: Vector2,
+    speed_scale: real,
+    // Scale of the sprite in the scene, for mobs of size 1.
+    sprite_scale: Vector2,
+    // Nodes of Mob.tscn, resolved and validated in ready().
+    sprite: OnReady<Gd<AnimatedSprite2D>>,
+    collision_shape: OnReady<Gd<CollisionShape2D>>,
+    notifier: OnReady<Gd<VisibleOnScreenNotifier2D>>,
+    nodes_resolved: bool,
     base: Base<RigidBody2D>,
 }
 
 #[godot_api]
 impl Mob {
+    // Mobs are pooled by Main, so instead of freeing themselves, they ask to be taken back.
+    #[signal]
+    pub fn despawn_requested();
+
+    // Emitted by splitters; Main spawns the fragments.
+    #[signal]
+    pub fn split_requested(position: Vector2, velocity: Vector2);
+
+    // Emitted by bosses; Main spawns the bullets. See MobAction::FireRing.
+    #[signal]
+    pub fn ring_fired(position: Vector2, bullets: u32, offset: real);
+
     #[func]
     fn on_visibility_screen_exited(&mut self) {
-        self.base_mut().queue_free();
+        self.despawn();
     }
 
-    #[func]
-    fn on_start_game(&mut self) {
-        self.base_mut().queue_free();
+    pub fn despawn(&mut self) {
+        self.signals().despawn_requested().emit();
     }
 }
 
@@ -27,23 +53,142 @@ impl Mob {
 impl IRigidBody2D for Mob {
     fn init(base: Base<RigidBody2D>) -> Self {
         Mob {
-            min_speed: 150.0,
-            max_speed: 250.0,
+            behaviour: Box::new(Straight),
+            velocity: Vector2::ZERO,
+            speed_scale: 1.0,
+            sprite_scale: Vector2::ONE,
+            sprite: OnReady::manual(),
+            collision_shape: OnReady::manual(),
+            notifier: OnReady::manual(),
+            nodes_resolved: false,
             base,
         }
     }
 
     fn ready(&mut self) {
-        let mut sprite = self
-            .base()
-            .get_node_as::<AnimatedSprite2D>("AnimatedSprite2D");
+        let mut nodes = NodeRefs::new(self.to_gd());
+        nodes.resolve(&mut self.sprite, "AnimatedSprite2D");
+        nodes.resolve(&mut self.collision_shape, "CollisionShape2D");
+        nodes.resolve(&mut self.notifier, "VisibleOnScreenNotifier2D");
+
+        self.nodes_resolved = nodes.finish();
+        if !self.nodes_resolved {
+            self.base_mut().set_physics_process(false);
+            return;
+        }
+
+        self.sprite_scale = self.sprite.get_scale();
+        self.sprite.play();
+    }
+
+    fn physics_process(&mut self, delta: f64) {
+        let position = self.base().get_global_position();
+        let ctx = MobContext {
+            position,
+            velocity: self.velocity,
+            target: if self.behaviour.needs_target() {
+                self.nearest_player(position)
+            } else {
+                None
+            },
+        };
+
+        match self.behaviour.update(&ctx, delta) {
+            MobAction::Move(velocity) => self.move_with(velocity),
+            MobAction::FireRing {
+                velocity,
+                bullets,
+                offset,
+            } => {
+                self.move_with(velocity);
+                self.signals().ring_fired().emit(position, bullets, offset);
+            }
+            MobAction::Split => {
+                // Splitting takes effect at the end of the frame; make sure it only happens once.
+                self.behaviour = Box::new(Straight);
+
+                let velocity = self.velocity;
+                self.signals().split_requested().emit(position, velocity);
+                self.despawn();
+            }
+        }
+    }
+}
+
+impl Mob {
+    /// Sets up a freshly spawned (or recycled) mob. Called by `Main` after adding it to the tree, so the random look comes from the
+    /// run's seeded RNG.
+    ///
+    /// `size` scales sprite and collision shape; 1 is a regular mob.
+    pub fn initialize(
+        &mut self,
+        rng: &mut impl Rng,
+        behaviour: Box<dyn MobBehaviour>,
+        velocity: Vector2,
+        size: real,
+    ) {
+        // The game is already quitting; see ready().
+        if !self.nodes_resolved {
+            return;
+        }
+
+        let anim_names = self
+            .sprite
+            .get_sprite_frames()
+            .unwrap()
+            .get_animation_names();
+        let index = rng.gen_range(0..anim_names.len());
+        let animation_name = anim_names.get(index).unwrap();
+
+        let sprite_scale = self.sprite_scale;
+        self.sprite.set_animation(animation_name.arg());
+        self.sprite.set_modulate(behaviour.tint());
+        self.sprite.set_scale(sprite_scale * size);
+
+        // Physics bodies themselves should not be scaled, but their shapes can.
+        self.collision_shape.set_scale(Vector2::splat(size));
+
+        // Large mobs should only despawn once they are completely off screen.
+        self.notifier.set_scale(Vector2::splat(size));
 
-        sprite.play();
-        let anim_names = sprite.get_sprite_frames().unwrap().get_animation_names();
+        self.behaviour = behaviour;
+        self.velocity = velocity;
+        self.base_mut().set_rotation(velocity.angle());
 
-        let anim_names = anim_names.to_typed_array();
-        let animation_name = anim_names.pick_random().unwrap();
+        let scaled = velocity * self.speed_scale;
+        self.base_mut().set_linear_velocity(scaled);
+    }
+
+    /// Factor for the mob's velocity, e.g. while slow motion is active.
+    pub fn set_speed_scale(&mut self, speed_scale: real) {
+        self.speed_scale = speed_scale;
+
+        let scaled = self.velocity * speed_scale;
+        self.base_mut().set_linear_velocity(scaled);
+    }
+
+    fn move_with(&mut self, velocity: Vector2) {
+        if velocity != self.velocity {
+            self.velocity = velocity;
+            self.base_mut().set_rotation(velocity.angle());
+        }
+
+        let scaled = velocity * self.speed_scale;
+        self.base_mut().set_linear_velocity(scaled);
+    }
 
-        sprite.set_animation(animation_name.arg());
+    fn nearest_player(&self, from: Vector2) -> Option<Vector2> {
+        // Players that are down are hidden.
+        self.base()
+            .get_tree()
+            .get_nodes_in_group("players")
+            .iter_shared()
+            .filter_map(|node| node.try_cast::<Node2D>().ok())
+            .filter(|player| player.is_visible())
+            .map(|player| player.get_global_position())
+            .min_by(|a, b| {
+                a.distance_squared_to(from)
+                    .total_cmp(&b.distance_squared_to(from))
+            })
     }
 }
@@ -0,0 +1,290 @@
+use godot::builtin::real_consts::{PI, TAU};
+use godot::prelude::*;
+
+use rand::Rng;
+
+/// What a behaviour knows about its mob and the world, in one physics frame.
+pub struct MobContext {
+    pub position: Vector2,
+    /// Current velocity, without slow motion applied.
+    pub velocity: Vector2,
+    /// Position of the nearest player still in the round, if any. Only looked up for behaviours that [need it](MobBehaviour::needs_target).
+    pub target: Option<Vector2>,
+}
+
+/// What a mob should do after its behaviour has been updated.
+pub enum MobAction {
+    /// Move with this velocity (before slow motion is applied).
+    Move(Vector2),
+    /// Break into smaller mobs and disappear.
+    Split,
+    /// Move with this velocity, and fire a ring of `bullets` small mobs, the first one in direction `offset` (radians).
+    FireRing {
+        velocity: Vector2,
+        bullets: u32,
+        offset: real,
+    },
+}
+
+/// Strategy that decides how a mob moves. Every mob owns one, chosen when it is spawned.
+///
+/// To add a new behaviour, implement this trait, add a variant to [`MobKind`] and list it in the mob table resource.
+pub trait MobBehaviour {
+    /// Called every physics frame.
+    fn update(&mut self, ctx: &MobContext, delta: f64) -> MobAction;
+
+    /// Whether [`update()`](Self::update) reads [`MobContext::target`]. Finding the nearest player is not free, so it's skipped otherwise.
+    fn needs_target(&self) -> bool {
+        false
+    }
+
+    /// Tint of the mob's sprite, so players can tell behaviours apart.
+    fn tint(&self) -> Color {
+        Color::WHITE
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Drifts in a straight line, like the mobs of the original game.
+pub struct Straight;
+
+impl MobBehaviour for Straight {
+    fn update(&mut self, ctx: &MobContext, _delta: f64) -> MobAction {
+        MobAction::Move(ctx.velocity)
+    }
+}
+
+/// Turns towards the nearest player for a while, then keeps its heading so that it eventually leaves the screen.
+pub struct Homing {
+    /// Maximum turn speed, in radians per second.
+    turn_rate: real,
+    /// Seconds of homing left.
+    remaining: f64,
+}
+
+impl MobBehaviour for Homing {
+    fn update(&mut self, ctx: &MobContext, delta: f64) -> MobAction {
+        self.remaining -= delta;
+
+        let Some(target) = ctx.target.filter(|_| self.remaining > 0.0) else {
+            return MobAction::Move(ctx.velocity);
+        };
+
+        let wanted = (target - ctx.position).angle();
+        let current = ctx.velocity.angle();
+
+        // Shortest signed angle from the current heading to the wanted one.
+        let difference = wrap_angle(wanted - current);
+        let max_turn = self.turn_rate * delta as real;
+        let turn = difference.clamp(-max_turn, max_turn);
+
+        MobAction::Move(ctx.velocity.rotated(turn))
+    }
+
+    fn needs_target(&self) -> bool {
+        self.remaining > 0.0
+    }
+
+    fn tint(&self) -> Color {
+        Color::from_rgb(1.0, 0.55, 0.55)
+    }
+}
+
+/// Weaves left and right around its initial heading.
+pub struct ZigZag {
+    /// Heading and speed of the overall movement. Set on the first update.
+    forward: Option<Vector2>,
+    /// Maximum sideways speed, as a fraction of the forward speed.
+    amplitude: real,
+    /// Full left-right cycles per second.
+    frequency: real,
+    time: real,
+}
+
+impl MobBehaviour for ZigZag {
+    fn update(&mut self, ctx: &MobContext, delta: f64) -> MobAction {
+        let forward = *self.forward.get_or_insert(ctx.velocity);
+        self.time += delta as real;
+
+        let phase = self.time * self.frequency * TAU;
+        let sideways = forward.orthogonal() * self.amplitude * phase.sin();
+
+        MobAction::Move(forward + sideways)
+    }
+
+    fn tint(&self) -> Color {
+        Color::from_rgb(0.55, 1.0, 0.6)
+    }
+}
+
+/// Drifts in a straight line, and breaks into smaller mobs once its fuse runs out.
+pub struct Splitter {
+    /// Seconds until it splits.
+    fuse: f64,
+}
+
+impl MobBehaviour for Splitter {
+    fn update(&mut self, ctx: &MobContext, delta: f64) -> MobAction {
+        self.fuse -= delta;
+
+        if self.fuse <= 0.0 {
+            MobAction::Split
+        } else {
+            MobAction::Move(ctx.velocity)
+        }
+    }
+
+    fn tint(&self) -> Color {
+        Color::from_rgb(0.7, 0.7, 1.0)
+    }
+}
+
+/// Crosses the screen slowly as a large mob, firing rings of bullets at a steady rhythm. Spawned by boss waves.
+pub struct Boss {
+    /// Seconds between two rings.
+    interval: f64,
+    /// Seconds until the next ring.
+    cooldown: f64,
+    /// Bullets per ring.
+    bullets: u32,
+    rings_fired: u32,
+}
+
+impl MobBehaviour for Boss {
+    fn update(&mut self, ctx: &MobContext, delta: f64) -> MobAction {
+        self.cooldown -= delta;
+        if self.cooldown > 0.0 {
+            return MobAction::Move(ctx.velocity);
+        }
+
+        self.cooldown += self.interval;
+        self.rings_fired += 1;
+
+        // Every other ring is rotated by half the gap between bullets, so that standing still is not an option.
+        let gap = TAU / self.bullets as real;
+        let offset = if self.rings_fired % 2 == 0 {
+            gap / 2.0
+        } else {
+            0.0
+        };
+
+        MobAction::FireRing {
+            velocity: ctx.velocity,
+            bullets: self.bullets,
+            offset,
+        }
+    }
+
+    fn tint(&self) -> Color {
+        Color::from_rgb(1.0, 0.8, 0.3)
+    }
+}
+
+fn wrap_angle(angle: real) -> real {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+#[derive(GodotConvert, Var, Export, Default, Copy, Clone, Debug, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum MobKind {
+    #[default]
+    Straight,
+    Homing,
+    ZigZag,
+    Splitter,
+    /// Only spawned by boss waves; mob tables ignore it.
+    Boss,
+}
+
+impl MobKind {
+    /// Creates the behaviour for a newly spawned mob. Its parameters vary a bit from mob to mob.
+    pub fn create_behaviour(self, rng: &mut impl Rng) -> Box<dyn MobBehaviour> {
+        match self {
+            Self::Straight => Box::new(Straight),
+            Self::Homing => Box::new(Homing {
+                turn_rate: rng.gen_range(0.8..1.4),
+                remaining: rng.gen_range(2.0..3.5),
+            }),
+            Self::ZigZag => Box::new(ZigZag {
+                forward: None,
+                amplitude: rng.gen_range(0.6..1.0),
+                frequency: rng.gen_range(0.8..1.5),
+                time: 0.0,
+            }),
+            Self::Splitter => Box::new(Splitter {
+                fuse: rng.gen_range(1.0..2.0),
+            }),
+            Self::Boss => Box::new(Boss {
+                interval: 1.6,
+                // Gives the boss time to come into view before the first ring.
+                cooldown: 2.0,
+                bullets: rng.gen_range(10..=14),
+                rings_fired: 0,
+            }),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// How likely a kind of mob is to be spawned, relative to the other entries of a [`MobTable`].
+#[derive(GodotClass)]
+#[class(init, base=Resource)]
+pub struct MobTableEntry {
+    #[export]
+    kind: MobKind,
+
+    /// Relative weight; entries with a weight of 0 are never picked.
+    #[export]
+    #[init(val = 1.0)]
+    weight: f32,
+
+    base: Base<Resource>,
+}
+
+/// Weighted table from which the kind of each spawned mob is drawn.
+#[derive(GodotClass)]
+#[class(init, base=Resource)]
+pub struct MobTable {
+    /// Empty slots are ignored, like entries with a weight of 0.
+    #[export]
+    entries: Array<Option<Gd<MobTableEntry>>>,
+
+    base: Base<Resource>,
+}
+
+impl MobTable {
+    /// Draws a kind according to the weights. An empty table only spawns straight mobs.
+    pub fn pick(&self, rng: &mut impl Rng) -> MobKind {
+        let entries: Vec<(MobKind, f32)> = self
+            .entries
+            .iter_shared()
+            .flatten()
+            .map(|entry| {
+                let entry = entry.bind();
+                (entry.kind, entry.weight)
+            })
+            .filter(|(kind, weight)| *weight > 0.0 && *kind != MobKind::Boss)
+            .collect();
+
+        if entries.is_empty() {
+            return MobKind::Straight;
+        }
+
+        let total: f32 = entries.iter().map(|(_, weight)| weight).sum();
+
+        let mut roll = rng.gen_range(0.0..total);
+        for (kind, weight) in &entries {
+            if roll < *weight {
+                return *kind;
+            }
+            roll -= weight;
+        }
+
+        // Only reachable through rounding errors.
+        entries.last().map_or(MobKind::Straight, |(kind, _)| *kind)
+    }
+}
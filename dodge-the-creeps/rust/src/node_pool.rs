@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use godot::obj::Inherits;
+use godot::prelude::*;
+
+/// Counters describing how a [`NodePool`] has been used.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct PoolStats {
+    /// Instances created from the scene, including pre-instantiated ones.
+    pub created: usize,
+    /// Acquisitions served by a recycled instance.
+    pub reused: usize,
+    /// Instances currently handed out.
+    pub active: usize,
+    /// Instances waiting to be reused.
+    pub pooled: usize,
+    /// Highest number of simultaneously active instances.
+    pub peak_active: usize,
+}
+
+impl fmt::Display for PoolStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} created, {} reused, {} active (peak {}), {} pooled",
+            self.created, self.reused, self.active, self.peak_active, self.pooled
+        )
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Recycles instances of a scene, instead of instantiating and freeing them over and over.
+///
+/// Acquired nodes are not part of the tree; the caller adds them wherever needed. Released nodes are removed from the tree and kept
+/// for the next [`acquire()`](Self::acquire), which skips the cost of instantiating the scene.
+pub struct NodePool<T>
+where
+    T: GodotClass + Inherits<Node>,
+{
+    scene: Gd<PackedScene>,
+    pooled: Vec<Gd<T>>,
+    active: HashMap<InstanceId, Gd<T>>,
+    on_create: Box<dyn FnMut(&Gd<T>)>,
+    stats: PoolStats,
+}
+
+impl<T> NodePool<T>
+where
+    T: GodotClass + Inherits<Node>,
+{
+    /// Creates a pool with `prewarm` instances ready to use.
+    ///
+    /// `on_create` runs once for every new instance, e.g. to connect signals; it is not repeated when an instance is reused.
+    pub fn new(
+        scene: Gd<PackedScene>,
+        prewarm: usize,
+        on_create: impl FnMut(&Gd<T>) + 'static,
+    ) -> Self {
+        let mut pool = Self {
+            scene,
+            pooled: Vec::with_capacity(prewarm),
+            active: HashMap::new(),
+            on_create: Box::new(on_create),
+            stats: PoolStats::default(),
+        };
+
+        for _ in 0..prewarm {
+            let node = pool.instantiate();
+            pool.pooled.push(node);
+        }
+
+        pool
+    }
+
+    /// Returns a recycled instance if one is available, otherwise a new one.
+    pub fn acquire(&mut self) -> Gd<T> {
+        let node = match self.pooled.pop() {
+            Some(node) => {
+                self.stats.reused += 1;
+                node
+            }
+            None => self.instantiate(),
+        };
+
+        self.active.insert(node.instance_id(), node.clone());
+        self.stats.peak_active = self.stats.peak_active.max(self.active.len());
+
+        node
+    }
+
+    /// Takes an instance back, removing it from the tree. Instances which are not active are ignored, so releasing twice is harmless.
+    pub fn release(&mut self, node: &Gd<T>) {
+        let Some(node) = self.active.remove(&node.instance_id()) else {
+            return;
+        };
+
+        let as_node = node.clone().upcast::<Node>();
+        if let Some(mut parent) = as_node.get_parent() {
+            parent.remove_child(&as_node);
+        }
+
+        self.pooled.push(node);
+    }
+
+    /// Takes back all active instances.
+    pub fn release_all(&mut self) {
+        let active: Vec<Gd<T>> = self.active.values().cloned().collect();
+        for node in &active {
+            self.release(node);
+        }
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            active: self.active.len(),
+            pooled: self.pooled.len(),
+            ..self.stats
+        }
+    }
+
+    fn instantiate(&mut self) -> Gd<T> {
+        let node = self.scene.instantiate_as::<T>();
+        (self.on_create)(&node);
+        self.stats.created += 1;
+
+        node
+    }
+}
+
+impl<T> Drop for NodePool<T>
+where
+    T: GodotClass + Inherits<Node>,
+{
+    // Pooled nodes are outside the tree, so nobody else frees them.
+    fn drop(&mut self) {
+        for node in self.pooled.drain(..) {
+            node.upcast::<Node>().free();
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use godot::obj::Inherits;
+use godot::prelude::*;
+
+/// Resolves the child nodes a class depends on, once when it becomes ready.
+///
+/// Each node is checked for existence and type. Instead of panicking at the first problem (or later, in the middle of a game), all
+/// problems of a scene are collected and reported together by [`finish()`](Self::finish).
+pub struct NodeRefs {
+    owner: Gd<Node>,
+    problems: Vec<String>,
+}
+
+impl NodeRefs {
+    pub fn new<T>(owner: Gd<T>) -> Self
+    where
+        T: GodotClass + Inherits<Node>,
+    {
+        Self {
+            owner: owner.upcast(),
+            problems: Vec::new(),
+        }
+    }
+
+    /// Initializes `field` with the node at `path`, relative to the owner. If there is no such node of type `T`, the problem is recorded
+    /// and `field` stays uninitialized.
+    pub fn resolve<T>(&mut self, field: &mut OnReady<Gd<T>>, path: &str)
+    where
+        T: GodotClass + Inherits<Node>,
+    {
+        // Same naming as get_class() below, e.g. "Label".
+        let expected = T::class_id();
+
+        let Some(node) = self.owner.get_node_or_null(path) else {
+            self.problems
+                .push(format!("missing node '{path}' of type {expected}"));
+            return;
+        };
+
+        match node.try_cast::<T>() {
+            Ok(node) => field.init(node),
+            Err(node) => self.problems.push(format!(
+                "node '{path}' is a {}, but should be a {expected}",
+                node.get_class()
+            )),
+        }
+    }
+
+    /// Reports all recorded problems as one error, and quits the game if there were any, since it cannot run without its nodes.
+    ///
+    /// Returns whether all nodes were resolved. If not, the caller must not use any of them.
+    pub fn finish(self) -> bool {
+        if self.problems.is_empty() {
+            return true;
+        }
+
+        let scene = self.owner.get_scene_file_path();
+        let scene = if scene.is_empty() {
+            self.owner.get_name().to_string()
+        } else {
+            scene.to_string()
+        };
+
+        godot_error!(
+            "{scene} does not have the nodes its script expects:\n  - {}",
+            self.problems.join("\n  - ")
+        );
+
+        self.owner.get_tree().quit_ex().exit_code(1).done();
+        false
+    }
+}
@@ -1,43 +1,234 @@
+use crate::combo::{Combo, NEAR_MISS_POINTS};
+use crate::mob::Mob;
+use crate::node_refs::NodeRefs;
+use crate::power_up::{ActiveEffects, PowerUpKind};
+use crate::replay::{InputRecording, InputSource};
+use crate::touch_input::TouchInput;
+
 use godot::classes::{AnimatedSprite2D, Area2D, CollisionShape2D, IArea2D, Input};
 use godot::prelude::*;
 
+use std::collections::HashSet;
+
+// Blinks per second while invulnerable.
+const BLINK_FREQUENCY: f64 = 8.0;
+
+/// Names of the input actions that move a player.
+#[derive(Clone)]
+pub struct MoveActions {
+    left: StringName,
+    right: StringName,
+    up: StringName,
+    down: StringName,
+}
+
+impl MoveActions {
+    /// Actions `<prefix>move_left`, `<prefix>move_right` etc. from the project's input map.
+    pub fn with_prefix(prefix: &str) -> Self {
+        let action =
+            |direction: &str| StringName::from(format!("{prefix}move_{direction}").as_str());
+
+        Self {
+            left: action("left"),
+            right: action("right"),
+            up: action("up"),
+            down: action("down"),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
 #[derive(GodotClass)]
 #[class(base=Area2D)]
 pub struct Player {
     speed: real,
     screen_size: Vector2,
 
+    /// Analog stick tilt below which the player does not move, between 0 and 1.
+    #[export]
+    stick_deadzone: f32,
+
+    /// Optional touch controls, used in addition to keyboard and gamepad.
+    #[export]
+    touch_input: Option<Gd<TouchInput>>,
+
+    /// Seconds during which the player cannot be hit after losing a life.
+    #[export]
+    invulnerability_time: f64,
+
+    // Lives left in the current round, and where the player respawns after losing one.
+    lives: i64,
+    spawn_position: Vector2,
+    // Seconds of invulnerability left.
+    invulnerability: f64,
+
+    actions: MoveActions,
+    input: InputSource,
+    effects: ActiveEffects,
+    combo: Combo,
+    // Mobs currently inside the near-miss area.
+    near_mobs: HashSet<InstanceId>,
+    // Mobs that touched the player (and popped the shield); passing them is no near miss.
+    touched_mobs: HashSet<InstanceId>,
+
+    // Nodes of Player.tscn, resolved and validated in ready().
+    sprite: OnReady<Gd<AnimatedSprite2D>>,
+    collision_shape: OnReady<Gd<CollisionShape2D>>,
+    near_miss_area: OnReady<Gd<Area2D>>,
+
     base: Base<Area2D>,
 }
 
 #[godot_api]
 impl Player {
-    // Public signal, since it's used by Main struct.
+    // Public signal, since it's used by Main struct. Emitted when the last life is lost, and the player drops out of the round.
     #[signal]
     pub fn hit();
 
+    // Emitted when a life is lost, including the last one.
+    #[signal]
+    pub fn lives_changed();
+
+    // Emitted when a power-up effect is gained or lost.
+    #[signal]
+    pub fn effects_changed();
+
+    // Emitted when a mob passed close by without hitting. `points` already include the combo `multiplier`.
+    #[signal]
+    pub fn near_miss(points: i64, multiplier: i64, position: Vector2);
+
     #[func]
-    fn on_player_body_entered(&mut self, _body: Gd<Node2D>) {
+    fn on_player_body_entered(&mut self, body: Gd<Node2D>) {
+        self.touched_mobs.insert(body.instance_id());
+
+        if self.invulnerability > 0.0 {
+            return;
+        }
+
+        if self.effects.shield {
+            // The shield pops and takes the mob with it.
+            self.effects.shield = false;
+            if let Ok(mut mob) = body.try_cast::<Mob>() {
+                mob.bind_mut().despawn();
+            }
+
+            self.signals().effects_changed().emit();
+            return;
+        }
+
+        self.take_hit();
+    }
+
+    /// Costs a life. The player respawns with a moment of invulnerability, or drops out of the round once out of lives.
+    pub fn take_hit(&mut self) {
+        self.lives = (self.lives - 1).max(0);
+
+        if self.lives > 0 {
+            let spawn_position = self.spawn_position;
+            self.base_mut().set_global_position(spawn_position);
+            self.set_invulnerability(self.invulnerability_time);
+
+            self.signals().lives_changed().emit();
+            return;
+        }
+
         self.base_mut().hide();
+        self.signals().lives_changed().emit();
         self.signals().hit().emit();
 
-        let mut collision_shape = self
-            .base()
-            .get_node_as::<CollisionShape2D>("CollisionShape2D");
-
-        collision_shape.set_deferred("disabled", &true.to_variant());
+        self.collision_shape
+            .set_deferred("disabled", &true.to_variant());
     }
 
+    /// Puts the player into a new round at `pos`, with `lives` lives.
     #[func]
-    pub fn start(&mut self, pos: Vector2) {
+    pub fn start(&mut self, pos: Vector2, lives: i64) {
         self.base_mut().set_global_position(pos);
         self.base_mut().show();
 
-        let mut collision_shape = self
-            .base()
-            .get_node_as::<CollisionShape2D>("CollisionShape2D");
+        self.spawn_position = pos;
+        self.lives = lives.max(1);
+        self.set_invulnerability(0.0);
+
+        self.collision_shape.set_disabled(false);
+
+        // Main refreshes the HUD itself after starting, so no effects_changed signal here.
+        self.effects = ActiveEffects::default();
+        self.combo = Combo::default();
+
+        // Mobs of the last round are about to be cleared away; they must not count as near misses.
+        self.near_mobs.clear();
+        self.touched_mobs.clear();
+    }
+
+    // No #[func], connected in pure Rust.
+    fn on_near_miss_area_body_entered(&mut self, body: Gd<Node2D>) {
+        self.near_mobs.insert(body.instance_id());
+    }
+
+    // No #[func], connected in pure Rust.
+    fn on_near_miss_area_body_exited(&mut self, body: Gd<Node2D>) {
+        let id = body.instance_id();
+        let was_near = self.near_mobs.remove(&id);
+        let was_touched = self.touched_mobs.remove(&id);
+
+        // Players that are down (or sit out the round) are hidden.
+        if !was_near || was_touched || !self.base().is_visible() {
+            return;
+        }
+
+        let multiplier = self.combo.register_near_miss();
+        let points = NEAR_MISS_POINTS * multiplier * self.effects.score_multiplier();
+        let position = self.base().get_global_position();
+
+        self.signals()
+            .near_miss()
+            .emit(points, multiplier, position);
+    }
+
+    pub fn effects(&self) -> &ActiveEffects {
+        &self.effects
+    }
+
+    pub fn lives(&self) -> i64 {
+        self.lives
+    }
+
+    // The sprite blinks while invulnerable. Only the sprite: the player itself stays visible, since hidden players count as down.
+    fn set_invulnerability(&mut self, seconds: f64) {
+        self.invulnerability = seconds.max(0.0);
+
+        if self.invulnerability > 0.0 {
+            let blink_on = (self.invulnerability * BLINK_FREQUENCY).fract() < 0.5;
+            self.sprite.set_visible(blink_on);
+        } else {
+            self.sprite.show();
+        }
+    }
 
-        collision_shape.set_disabled(false);
+    // No #[func], called by PowerUp.
+    pub fn apply_power_up(&mut self, kind: PowerUpKind, duration: f64) {
+        self.effects.apply(kind, duration);
+        self.signals().effects_changed().emit();
+    }
+
+    // No #[func], MoveActions is a Rust-only type.
+    pub fn set_move_actions(&mut self, actions: MoveActions) {
+        self.actions = actions;
+    }
+
+    // No #[func], InputSource is a Rust-only type.
+    pub fn set_input_source(&mut self, input: InputSource) {
+        self.input = input;
+    }
+
+    /// Switches back to live input and returns what has been recorded, if recording.
+    pub fn take_recording(&mut self) -> Option<InputRecording> {
+        match std::mem::replace(&mut self.input, InputSource::Live) {
+            InputSource::Recording(recording) => Some(recording),
+            _ => None,
+        }
     }
 }
 
@@ -47,6 +238,22 @@ impl IArea2D for Player {
         Player {
             speed: 400.0,
             screen_size: Vector2::new(0.0, 0.0),
+            stick_deadzone: 0.2,
+            touch_input: None,
+            invulnerability_time: 2.0,
+            lives: 1,
+            spawn_position: Vector2::ZERO,
+            invulnerability: 0.0,
+            // Main picks the action set when a round starts: all keys in solo mode, one half of the keyboard each in co-op.
+            actions: MoveActions::with_prefix(""),
+            input: InputSource::Live,
+            effects: ActiveEffects::default(),
+            combo: Combo::default(),
+            near_mobs: HashSet::new(),
+            touched_mobs: HashSet::new(),
+            sprite: OnReady::manual(),
+            collision_shape: OnReady::manual(),
+            near_miss_area: OnReady::manual(),
             base,
         }
     }
@@ -56,41 +263,74 @@ impl IArea2D for Player {
         self.screen_size = viewport.size;
         self.base_mut().hide();
 
+        let mut nodes = NodeRefs::new(self.to_gd());
+        nodes.resolve(&mut self.sprite, "AnimatedSprite2D");
+        nodes.resolve(&mut self.collision_shape, "CollisionShape2D");
+        nodes.resolve(&mut self.near_miss_area, "NearMissArea");
+
+        if !nodes.finish() {
+            self.base_mut().set_physics_process(false);
+            return;
+        }
+
+        // Not in play until start() is called; otherwise a player that sits out a round (player 2 in solo mode) could be hit or collect
+        // power-ups while hidden.
+        self.collision_shape.set_disabled(true);
+
         // Signal setup
         self.signals()
             .body_entered()
             .connect_self(Self::on_player_body_entered);
+
+        // The near-miss area is larger than the player's own shape, so every mob that hits has passed through it first.
+        let player = self.to_gd();
+        self.near_miss_area
+            .signals()
+            .body_entered()
+            .connect_other(&player, Self::on_near_miss_area_body_entered);
+        self.near_miss_area
+            .signals()
+            .body_exited()
+            .connect_other(&player, Self::on_near_miss_area_body_exited);
     }
 
+    // Movement runs in fixed physics steps, so that a replay of the per-frame input moves the player exactly as in the original run.
     // `delta` can be f32 or f64; #[godot_api] macro converts transparently.
-    fn process(&mut self, delta: f32) {
-        let mut animated_sprite = self
-            .base()
-            .get_node_as::<AnimatedSprite2D>("AnimatedSprite2D");
-
-        let mut velocity = Vector2::new(0.0, 0.0);
+    fn physics_process(&mut self, delta: f32) {
+        let mut animated_sprite = self.sprite.clone();
 
-        // Note: exact=false by default, in Rust we have to provide it explicitly
-        let input = Input::singleton();
-        if input.is_action_pressed("move_right") {
-            velocity += Vector2::RIGHT;
-        }
-        if input.is_action_pressed("move_left") {
-            velocity += Vector2::LEFT;
+        if self.effects.tick(delta as f64) {
+            self.signals().effects_changed().emit();
         }
-        if input.is_action_pressed("move_down") {
-            velocity += Vector2::DOWN;
-        }
-        if input.is_action_pressed("move_up") {
-            velocity += Vector2::UP;
+
+        self.combo.tick(delta as f64);
+
+        if self.invulnerability > 0.0 {
+            self.set_invulnerability(self.invulnerability - delta as f64);
+
+            // Mobs still overlapping once invulnerability ends hit right away; they won't enter the shape a second time.
+            if self.invulnerability == 0.0 {
+                if let Some(body) = self.base().get_overlapping_bodies().front() {
+                    self.on_player_body_entered(body);
+                }
+            }
         }
 
-        if velocity.length() > 0.0 {
-            velocity = velocity.normalized() * self.speed;
+        let deadzone = self.stick_deadzone;
+        let actions = &self.actions;
+        let touch_input = self.touch_input.clone();
+        let position = self.base().get_global_position();
+        let direction = self
+            .input
+            .next_frame(|| live_direction(actions, deadzone, touch_input, position));
+
+        // Analog input moves the player slower than full speed.
+        let velocity = direction * self.speed;
 
+        if velocity.length() > 0.0 {
             let animation;
 
-            if velocity.x != 0.0 {
+            if velocity.x.abs() >= velocity.y.abs() {
                 animation = "right";
 
                 animated_sprite.set_flip_v(false);
@@ -107,7 +347,7 @@ impl IArea2D for Player {
         }
 
         let change = velocity * delta;
-        let position = self.base().get_global_position() + change;
+        let position = position + change;
         let position = Vector2::new(
             position.x.clamp(0.0, self.screen_size.x),
             position.y.clamp(0.0, self.screen_size.y),
@@ -115,3 +355,23 @@ impl IArea2D for Player {
         self.base_mut().set_global_position(position);
     }
 }
+
+/// Combined direction of keyboard, gamepad and touch input, with a length of at most 1.
+fn live_direction(
+    actions: &MoveActions,
+    stick_deadzone: f32,
+    touch_input: Option<Gd<TouchInput>>,
+    player_position: Vector2,
+) -> Vector2 {
+    // Keys count as a fully tilted stick; diagonals are limited to length 1 as well.
+    let stick = Input::singleton()
+        .get_vector_ex(&actions.left, &actions.right, &actions.up, &actions.down)
+        .deadzone(stick_deadzone)
+        .done();
+
+    let touch = touch_input
+        .map(|touch_input| touch_input.bind().direction(player_position))
+        .unwrap_or_default();
+
+    (stick + touch).limit_length(Some(1.0))
+}
@@ -0,0 +1,170 @@
+use crate::player::Player;
+
+use godot::classes::{Area2D, IArea2D, ThemeDB};
+use godot::global::HorizontalAlignment;
+use godot::prelude::*;
+
+const RADIUS: real = 20.0;
+
+// While slow motion is active, mobs move at this fraction of their speed.
+const SLOW_MOTION_SCALE: real = 0.5;
+
+// While the multiplier is active, each score tick is worth this many points.
+const SCORE_MULTIPLIER: i64 = 2;
+
+// How long before expiring a pickup starts blinking, in seconds.
+const BLINK_TIME: f64 = 1.5;
+
+#[derive(GodotConvert, Var, Export, Default, Copy, Clone, Debug, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum PowerUpKind {
+    /// Absorbs the next hit.
+    #[default]
+    Shield,
+    /// Slows down all mobs for a while.
+    SlowMotion,
+    /// Doubles the score gained for a while.
+    ScoreMultiplier,
+}
+
+impl PowerUpKind {
+    pub const ALL: [Self; 3] = [Self::Shield, Self::SlowMotion, Self::ScoreMultiplier];
+
+    fn color(self) -> Color {
+        match self {
+            Self::Shield => Color::from_rgb(0.3, 0.6, 1.0),
+            Self::SlowMotion => Color::from_rgb(0.6, 0.3, 0.9),
+            Self::ScoreMultiplier => Color::from_rgb(1.0, 0.8, 0.2),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Shield => "S",
+            Self::SlowMotion => "T",
+            Self::ScoreMultiplier => "x2",
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Power-up effects currently held by the player.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub struct ActiveEffects {
+    pub shield: bool,
+    /// Seconds of slow motion left.
+    pub slow_motion: f64,
+    /// Seconds of score multiplier left.
+    pub score_multiplier: f64,
+}
+
+impl ActiveEffects {
+    pub fn apply(&mut self, kind: PowerUpKind, duration: f64) {
+        match kind {
+            PowerUpKind::Shield => self.shield = true,
+            PowerUpKind::SlowMotion => self.slow_motion = duration,
+            PowerUpKind::ScoreMultiplier => self.score_multiplier = duration,
+        }
+    }
+
+    /// Counts down timed effects. Returns true if one of them ran out.
+    pub fn tick(&mut self, delta: f64) -> bool {
+        let before = (self.slow_motion > 0.0, self.score_multiplier > 0.0);
+
+        self.slow_motion = (self.slow_motion - delta).max(0.0);
+        self.score_multiplier = (self.score_multiplier - delta).max(0.0);
+
+        before != (self.slow_motion > 0.0, self.score_multiplier > 0.0)
+    }
+
+    /// Factor for the velocity of all mobs.
+    pub fn mob_speed_scale(&self) -> real {
+        if self.slow_motion > 0.0 {
+            SLOW_MOTION_SCALE
+        } else {
+            1.0
+        }
+    }
+
+    /// Points awarded per score tick.
+    pub fn score_multiplier(&self) -> i64 {
+        if self.score_multiplier > 0.0 {
+            SCORE_MULTIPLIER
+        } else {
+            1
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// A pickup that grants the player an effect on contact, and vanishes after a while if not collected.
+#[derive(GodotClass)]
+#[class(init, base=Area2D)]
+pub struct PowerUp {
+    #[export]
+    kind: PowerUpKind,
+
+    /// Seconds until the pickup disappears.
+    #[export]
+    #[init(val = 6.0)]
+    lifetime: f64,
+
+    /// Seconds that timed effects last once collected.
+    #[export]
+    #[init(val = 5.0)]
+    effect_duration: f64,
+
+    base: Base<Area2D>,
+}
+
+#[godot_api]
+impl IArea2D for PowerUp {
+    fn ready(&mut self) {
+        self.signals()
+            .area_entered()
+            .connect_self(Self::on_area_entered);
+    }
+
+    fn physics_process(&mut self, delta: f64) {
+        self.lifetime -= delta;
+
+        if self.lifetime <= 0.0 {
+            self.base_mut().queue_free();
+        } else if self.lifetime < BLINK_TIME {
+            let visible = (self.lifetime * 8.0) as i64 % 2 == 0;
+            self.base_mut().set_visible(visible);
+        }
+    }
+
+    fn draw(&mut self) {
+        let color = self.kind.color();
+        self.base_mut().draw_circle(Vector2::ZERO, RADIUS, color);
+
+        if let Some(font) = ThemeDB::singleton().get_fallback_font() {
+            let label = self.kind.label();
+            self.base_mut()
+                .draw_string_ex(&font, Vector2::new(-RADIUS, 6.0), label)
+                .alignment(HorizontalAlignment::CENTER)
+                .width(RADIUS * 2.0)
+                .done();
+        }
+    }
+}
+
+#[godot_api]
+impl PowerUp {
+    // No #[func], connected in pure Rust.
+    fn on_area_entered(&mut self, area: Gd<Area2D>) {
+        let Ok(mut player) = area.try_cast::<Player>() else {
+            return;
+        };
+
+        player
+            .bind_mut()
+            .apply_power_up(self.kind, self.effect_duration);
+
+        self.base_mut().queue_free();
+    }
+}
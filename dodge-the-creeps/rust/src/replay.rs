@@ -0,0 +1,181 @@
+use godot::classes::file_access::ModeFlags;
+use godot::classes::FileAccess;
+use godot::prelude::*;
+
+// "DTCR" in little endian, to recognize replay files.
+const MAGIC: u32 = u32::from_le_bytes(*b"DTCR");
+
+// Bump this whenever the file layout changes.
+const VERSION: u8 = 2;
+
+/// Whether runs are recorded or played back.
+#[derive(GodotConvert, Var, Export, Default, Copy, Clone, Debug, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum ReplayMode {
+    /// Live input only.
+    #[default]
+    Off,
+    /// Live input, saved to a file when the run ends.
+    Record,
+    /// Input is read from a file instead of the devices.
+    Play,
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Movement input during one physics frame, quantized to one signed byte per axis.
+///
+/// Live input is quantized too, so that a replay moves the player exactly like the original run.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Debug)]
+struct InputFrame {
+    x: i8,
+    y: i8,
+}
+
+impl InputFrame {
+    /// Quantizes a direction; its length is limited to 1.
+    fn from_direction(direction: Vector2) -> Self {
+        let direction = direction.limit_length(Some(1.0));
+        let quantize = |value: real| (value * i8::MAX as real).round() as i8;
+
+        Self {
+            x: quantize(direction.x),
+            y: quantize(direction.y),
+        }
+    }
+
+    /// Direction with a length of at most 1.
+    fn direction(self) -> Vector2 {
+        Vector2::new(self.x as real, self.y as real) / i8::MAX as real
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Per-frame input of a run, together with the seed that the run was played with.
+///
+/// On disk, consecutive frames with the same state are stored as a single `(state, count)` pair, which keeps files small.
+pub struct InputRecording {
+    seed: i64,
+    frames: Vec<InputFrame>,
+}
+
+impl InputRecording {
+    pub fn new(seed: i64) -> Self {
+        Self {
+            seed,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn seed(&self) -> i64 {
+        self.seed
+    }
+
+    /// Reads a recording written by [`save()`](Self::save). Returns `None` if the file is missing or invalid.
+    pub fn load(path: &GString) -> Option<Self> {
+        let Some(file) = FileAccess::open(path, ModeFlags::READ) else {
+            godot_warn!("Could not open replay {path}.");
+            return None;
+        };
+
+        if file.get_32() != MAGIC || file.get_8() != VERSION {
+            godot_warn!("{path} is not a replay, or was recorded with an incompatible version.");
+            return None;
+        }
+
+        let seed = file.get_64() as i64;
+        let run_count = file.get_32();
+
+        let mut frames = Vec::new();
+        for _ in 0..run_count {
+            let frame = InputFrame {
+                x: file.get_8() as i8,
+                y: file.get_8() as i8,
+            };
+            let count = file.get_16();
+            frames.extend(std::iter::repeat_n(frame, count as usize));
+        }
+
+        if file.eof_reached() {
+            godot_warn!("Replay {path} is truncated.");
+            return None;
+        }
+
+        Some(Self { seed, frames })
+    }
+
+    pub fn save(&self, path: &GString) {
+        let Some(mut file) = FileAccess::open(path, ModeFlags::WRITE) else {
+            godot_warn!("Could not write replay to {path}.");
+            return;
+        };
+
+        // Run-length encoding; each run holds at most u16::MAX frames.
+        let mut runs: Vec<(InputFrame, u16)> = Vec::new();
+        for &frame in &self.frames {
+            match runs.last_mut() {
+                Some((last, count)) if *last == frame && *count < u16::MAX => *count += 1,
+                _ => runs.push((frame, 1)),
+            }
+        }
+
+        file.store_32(MAGIC);
+        file.store_8(VERSION);
+        file.store_64(self.seed as u64);
+        file.store_32(runs.len() as u32);
+        for (frame, count) in runs {
+            file.store_8(frame.x as u8);
+            file.store_8(frame.y as u8);
+            file.store_16(count);
+        }
+
+        godot_print!("Saved replay with {} frames to {path}.", self.frames.len());
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Where the player's input comes from.
+pub enum InputSource {
+    /// Input devices.
+    Live,
+    /// Input devices; every frame is appended to the recording.
+    Recording(InputRecording),
+    /// Frames of a previous recording. Once they run out, the player stands still.
+    Replay {
+        recording: InputRecording,
+        next_frame: usize,
+    },
+}
+
+impl InputSource {
+    pub fn replay(recording: InputRecording) -> Self {
+        Self::Replay {
+            recording,
+            next_frame: 0,
+        }
+    }
+
+    /// Returns the movement direction for the current physics frame. Must be called exactly once per frame.
+    ///
+    /// `sample_live` reads the input devices; it is not called during a replay.
+    pub fn next_frame(&mut self, sample_live: impl FnOnce() -> Vector2) -> Vector2 {
+        match self {
+            Self::Live => InputFrame::from_direction(sample_live()).direction(),
+            Self::Recording(recording) => {
+                let frame = InputFrame::from_direction(sample_live());
+                recording.frames.push(frame);
+                frame.direction()
+            }
+            Self::Replay {
+                recording,
+                next_frame,
+            } => {
+                let frame = recording.frames.get(*next_frame).copied();
+                *next_frame += 1;
+                frame.unwrap_or_default().direction()
+            }
+        }
+    }
+}
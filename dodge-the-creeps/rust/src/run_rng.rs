@@ -0,0 +1,50 @@
+use rand::rngs::StdRng;
+use rand::{Rng as _, RngCore, SeedableRng as _};
+
+/// Random number generator for a single run.
+///
+/// All gameplay randomness is drawn from here instead of `rand::thread_rng()`, so that a run can be reproduced from its seed.
+pub struct RunRng {
+    seed: i64,
+    rng: StdRng,
+}
+
+impl RunRng {
+    /// Creates a generator from `seed`. A seed of 0 picks a new random seed.
+    pub fn new(seed: i64) -> Self {
+        let seed = if seed == 0 {
+            rand::thread_rng().gen_range(1..=i64::MAX)
+        } else {
+            seed
+        };
+
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed as u64),
+        }
+    }
+
+    /// The seed actually in use; enter it as `Main.seed` to replay the run.
+    pub fn seed(&self) -> i64 {
+        self.seed
+    }
+}
+
+// Makes all of rand's `Rng` methods available on RunRng.
+impl RngCore for RunRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
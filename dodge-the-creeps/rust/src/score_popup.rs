@@ -0,0 +1,34 @@
+use godot::classes::{ILabel, Label};
+use godot::prelude::*;
+
+// Seconds until the popup has faded out completely.
+const LIFETIME: f64 = 0.9;
+
+// Upward movement, in pixels per second.
+const RISE_SPEED: real = 70.0;
+
+/// Floating text for points gained, which drifts upwards, fades out and frees itself.
+#[derive(GodotClass)]
+#[class(init, base=Label)]
+pub struct ScorePopup {
+    age: f64,
+    base: Base<Label>,
+}
+
+#[godot_api]
+impl ILabel for ScorePopup {
+    fn process(&mut self, delta: f64) {
+        self.age += delta;
+        if self.age >= LIFETIME {
+            self.base_mut().queue_free();
+            return;
+        }
+
+        let position = self.base().get_position() + Vector2::UP * RISE_SPEED * delta as real;
+        self.base_mut().set_position(position);
+
+        let mut modulate = self.base().get_modulate();
+        modulate.a = (1.0 - self.age / LIFETIME) as f32;
+        self.base_mut().set_modulate(modulate);
+    }
+}
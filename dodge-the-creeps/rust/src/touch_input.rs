@@ -0,0 +1,156 @@
+use godot::classes::{INode2D, InputEvent, InputEventScreenDrag, InputEventScreenTouch, Node2D};
+use godot::prelude::*;
+
+const COLOR: Color = Color::from_rgba(1.0, 1.0, 1.0, 0.35);
+
+// Radius of the joystick knob and of the drag target marker, in pixels.
+const KNOB_RADIUS: real = 24.0;
+
+#[derive(GodotConvert, Var, Export, Default, Copy, Clone, Debug, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum TouchMode {
+    /// A joystick appears where the finger touches the screen; moving the finger away from that point tilts it.
+    #[default]
+    Joystick,
+    /// The player walks towards the finger.
+    DragToMove,
+}
+
+// The finger currently steering; other fingers are ignored.
+struct Touch {
+    index: i32,
+    origin: Vector2,
+    current: Vector2,
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Turns touch screen input into a movement direction, for mobile devices.
+#[derive(GodotClass)]
+#[class(init, base=Node2D)]
+pub struct TouchInput {
+    #[export]
+    mode: TouchMode,
+
+    /// Distance from the joystick's center at which it is fully tilted, in pixels.
+    #[export]
+    #[init(val = 80.0)]
+    joystick_radius: real,
+
+    /// Fraction of the joystick radius in which it does not react.
+    #[export]
+    #[init(val = 0.2)]
+    joystick_deadzone: real,
+
+    /// In drag-to-move mode, the player slows down once it is closer than this to the finger, in pixels.
+    #[export]
+    #[init(val = 60.0)]
+    drag_slowdown_distance: real,
+
+    touch: Option<Touch>,
+
+    base: Base<Node2D>,
+}
+
+#[godot_api]
+impl INode2D for TouchInput {
+    fn unhandled_input(&mut self, event: Gd<InputEvent>) {
+        let event = match event.try_cast::<InputEventScreenTouch>() {
+            Ok(touch) => {
+                self.on_touch(touch);
+                return;
+            }
+            Err(event) => event,
+        };
+
+        if let Ok(drag) = event.try_cast::<InputEventScreenDrag>() {
+            self.on_drag(drag);
+        }
+    }
+
+    fn draw(&mut self) {
+        let Some(touch) = &self.touch else {
+            return;
+        };
+
+        let (origin, current) = (touch.origin, touch.current);
+        match self.mode {
+            TouchMode::Joystick => {
+                let radius = self.joystick_radius;
+                let knob = origin + (current - origin).limit_length(Some(radius));
+
+                self.base_mut()
+                    .draw_arc_ex(origin, radius, 0.0, std::f32::consts::TAU, 48, COLOR)
+                    .width(3.0)
+                    .done();
+                self.base_mut().draw_circle(knob, KNOB_RADIUS, COLOR);
+            }
+            TouchMode::DragToMove => {
+                self.base_mut().draw_circle(current, KNOB_RADIUS, COLOR);
+            }
+        }
+    }
+}
+
+impl TouchInput {
+    /// Movement direction with a length of at most 1, or zero if the screen is not touched.
+    ///
+    /// `player_position` is only relevant in drag-to-move mode.
+    pub fn direction(&self, player_position: Vector2) -> Vector2 {
+        let Some(touch) = &self.touch else {
+            return Vector2::ZERO;
+        };
+
+        match self.mode {
+            TouchMode::Joystick => {
+                let tilt = (touch.current - touch.origin) / self.joystick_radius.max(1.0);
+                if tilt.length() < self.joystick_deadzone {
+                    Vector2::ZERO
+                } else {
+                    tilt.limit_length(Some(1.0))
+                }
+            }
+            TouchMode::DragToMove => {
+                let offset = touch.current - player_position;
+                (offset / self.drag_slowdown_distance.max(1.0)).limit_length(Some(1.0))
+            }
+        }
+    }
+
+    fn on_touch(&mut self, event: Gd<InputEventScreenTouch>) {
+        let index = event.get_index();
+        let position = event.get_position();
+
+        let steering = self.touch.as_ref().map(|touch| touch.index);
+
+        match (steering, event.is_pressed()) {
+            (None, true) => {
+                self.touch = Some(Touch {
+                    index,
+                    origin: position,
+                    current: position,
+                });
+            }
+            (Some(steering), false) if steering == index => self.touch = None,
+            _ => return,
+        }
+
+        self.base().get_viewport().unwrap().set_input_as_handled();
+        self.base_mut().queue_redraw();
+    }
+
+    fn on_drag(&mut self, event: Gd<InputEventScreenDrag>) {
+        let Some(touch) = &mut self.touch else {
+            return;
+        };
+
+        if touch.index != event.get_index() {
+            return;
+        }
+
+        touch.current = event.get_position();
+
+        self.base().get_viewport().unwrap().set_input_as_handled();
+        self.base_mut().queue_redraw();
+    }
+}
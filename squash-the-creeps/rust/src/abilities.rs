@@ -0,0 +1,212 @@
+use godot::prelude::*;
+
+/// State of the player's movement that abilities read and change, in one physics frame.
+pub struct Motion {
+    /// Velocity the player is about to move with.
+    pub velocity: Vector3,
+    pub on_floor: bool,
+    /// Whether the player jumped off the ground (or a ledge) this frame.
+    pub jumped: bool,
+    /// Input direction on the ground plane; zero if no direction is held.
+    pub direction: Vector3,
+    /// Direction the player's model is facing, on the ground plane.
+    pub facing: Vector3,
+    /// Whether gravity applies this frame. Abilities that take over vertical movement turn it off.
+    pub gravity: bool,
+}
+
+/// Something an ability did that affects the world, not just the player.
+pub enum Impact {
+    /// Squash all mobs within `radius` meters around the player.
+    Shockwave { radius: f32 },
+}
+
+/// A move the player can make on top of walking and jumping.
+///
+/// To add a new ability, implement this trait, and add it (together with its tuning exports) to `Player`.
+pub trait Ability {
+    /// Input action that triggers the ability.
+    fn action(&self) -> &'static str;
+
+    /// Called when the action was just pressed. Returns whether the ability was used.
+    fn trigger(&mut self, motion: &mut Motion) -> bool;
+
+    /// Whether using the ability uses up a buffered jump press, so that landing shortly after does not jump as well.
+    fn consumes_jump_press(&self) -> bool {
+        false
+    }
+
+    /// Called every physics frame, after all triggers: counts down cooldowns and keeps ongoing moves going.
+    fn update(&mut self, motion: &mut Motion, delta: f64) -> Option<Impact>;
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Jumps again in mid-air, a limited number of times until landing.
+pub struct DoubleJump {
+    impulse: f32,
+    air_jumps: u32,
+    jumps_left: u32,
+}
+
+impl DoubleJump {
+    pub fn new(impulse: f32, air_jumps: u32) -> Self {
+        Self {
+            impulse,
+            air_jumps,
+            jumps_left: air_jumps,
+        }
+    }
+}
+
+impl Ability for DoubleJump {
+    fn action(&self) -> &'static str {
+        "jump"
+    }
+
+    fn trigger(&mut self, motion: &mut Motion) -> bool {
+        // A press that made the player leave the ground is a regular jump.
+        if motion.on_floor || motion.jumped || self.jumps_left == 0 {
+            return false;
+        }
+
+        self.jumps_left -= 1;
+        motion.velocity.y = self.impulse;
+        true
+    }
+
+    fn consumes_jump_press(&self) -> bool {
+        true
+    }
+
+    fn update(&mut self, motion: &mut Motion, _delta: f64) -> Option<Impact> {
+        if motion.on_floor {
+            self.jumps_left = self.air_jumps;
+        }
+
+        None
+    }
+}
+
+/// Bursts forward in mid-air, in the held direction (or the facing one), ignoring gravity for a moment.
+pub struct AirDash {
+    speed: f32,
+    /// Seconds the dash lasts.
+    duration: f64,
+    /// Seconds after a dash until the next one.
+    cooldown: f64,
+    direction: Vector3,
+    time_left: f64,
+    cooldown_left: f64,
+    /// Only one dash per time in the air.
+    available: bool,
+}
+
+impl AirDash {
+    pub fn new(speed: f32, duration: f64, cooldown: f64) -> Self {
+        Self {
+            speed,
+            duration,
+            cooldown,
+            direction: Vector3::ZERO,
+            time_left: 0.0,
+            cooldown_left: 0.0,
+            available: true,
+        }
+    }
+}
+
+impl Ability for AirDash {
+    fn action(&self) -> &'static str {
+        "dash"
+    }
+
+    fn trigger(&mut self, motion: &mut Motion) -> bool {
+        if motion.on_floor || !self.available || self.cooldown_left > 0.0 {
+            return false;
+        }
+
+        self.direction = if motion.direction == Vector3::ZERO {
+            motion.facing
+        } else {
+            motion.direction
+        };
+        self.time_left = self.duration;
+        self.cooldown_left = self.cooldown;
+        self.available = false;
+        true
+    }
+
+    fn update(&mut self, motion: &mut Motion, delta: f64) -> Option<Impact> {
+        self.cooldown_left = (self.cooldown_left - delta).max(0.0);
+        if motion.on_floor {
+            self.available = true;
+        }
+
+        if self.time_left > 0.0 {
+            self.time_left -= delta;
+            motion.velocity = self.direction * self.speed;
+            motion.gravity = false;
+        }
+
+        None
+    }
+}
+
+/// Slams straight down from mid-air; landing squashes all mobs around the player.
+pub struct GroundPound {
+    /// Downward speed while pounding.
+    speed: f32,
+    /// Meters around the landing spot in which mobs are squashed.
+    radius: f32,
+    /// Seconds after landing until the next pound.
+    cooldown: f64,
+    pounding: bool,
+    cooldown_left: f64,
+}
+
+impl GroundPound {
+    pub fn new(speed: f32, radius: f32, cooldown: f64) -> Self {
+        Self {
+            speed,
+            radius,
+            cooldown,
+            pounding: false,
+            cooldown_left: 0.0,
+        }
+    }
+}
+
+impl Ability for GroundPound {
+    fn action(&self) -> &'static str {
+        "ground_pound"
+    }
+
+    fn trigger(&mut self, motion: &mut Motion) -> bool {
+        if motion.on_floor || self.pounding || self.cooldown_left > 0.0 {
+            return false;
+        }
+
+        self.pounding = true;
+        true
+    }
+
+    fn update(&mut self, motion: &mut Motion, delta: f64) -> Option<Impact> {
+        self.cooldown_left = (self.cooldown_left - delta).max(0.0);
+        if !self.pounding {
+            return None;
+        }
+
+        if motion.on_floor {
+            self.pounding = false;
+            self.cooldown_left = self.cooldown;
+            return Some(Impact::Shockwave {
+                radius: self.radius,
+            });
+        }
+
+        motion.velocity = Vector3::DOWN * self.speed;
+        motion.gravity = false;
+        None
+    }
+}
@@ -0,0 +1,28 @@
+use demo_common::achievements::Achievement;
+
+// Checked by MainScene as the corresponding gameplay events arrive.
+// Titles and descriptions are translation keys, see the catalogs in translations/.
+
+pub const FIRST_SQUASH: Achievement = Achievement {
+    id: "first_squash",
+    title: "ACH_FIRST_SQUASH",
+    description: "ACH_FIRST_SQUASH_DESC",
+};
+
+pub const CHAIN_REACTION: Achievement = Achievement {
+    id: "chain_5",
+    title: "ACH_CHAIN_REACTION",
+    description: "ACH_CHAIN_REACTION_DESC",
+};
+
+pub const EXTERMINATOR: Achievement = Achievement {
+    id: "score_25",
+    title: "ACH_EXTERMINATOR",
+    description: "ACH_EXTERMINATOR_DESC",
+};
+
+pub const SURVIVOR: Achievement = Achievement {
+    id: "survive_60s",
+    title: "ACH_SURVIVOR",
+    description: "ACH_SURVIVOR_DESC",
+};
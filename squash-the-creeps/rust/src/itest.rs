@@ -0,0 +1,168 @@
+use crate::main_scene::MainScene;
+use crate::mob::Mob;
+use crate::mob_archetype::MobKind;
+use crate::player::Player;
+use crate::run_rng::RunRng;
+use crate::scorelabel::UserInterface;
+
+use demo_common::itest::{run_tests, TestCase, TestContext, TestFuture};
+use demo_common::locale::tr_format;
+use demo_common::{check, check_eq};
+use godot::classes::{ColorRect, Label, Timer};
+use godot::prelude::*;
+
+/// Runs the gameplay tests when `res://itest.tscn` is launched; see `demo_common::itest`.
+#[derive(GodotClass)]
+#[class(init, base=Node)]
+pub struct ItestRunner {
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for ItestRunner {
+    fn ready(&mut self) {
+        run_tests(self.to_gd().upcast(), TESTS);
+    }
+}
+
+const TESTS: &[TestCase] = &[
+    TestCase {
+        name: "squashing_mob_increments_score",
+        run: squashing_mob_increments_score,
+    },
+    TestCase {
+        name: "chain_multiplies_squash_points",
+        run: chain_multiplies_squash_points,
+    },
+    TestCase {
+        name: "armoured_mob_needs_two_stomps",
+        run: armoured_mob_needs_two_stomps,
+    },
+    TestCase {
+        name: "player_hit_shows_retry",
+        run: player_hit_shows_retry,
+    },
+];
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+// Tests must not touch the player's save data, e.g. by unlocking achievements for real.
+fn add_main(ctx: &TestContext) -> Gd<MainScene> {
+    ctx.add_scene_with::<MainScene>("res://main.tscn", |main| {
+        main.set("persist_progress", &false.to_variant());
+    })
+}
+
+fn squashing_mob_increments_score(ctx: TestContext) -> TestFuture {
+    Box::pin(async move {
+        let main = add_main(&ctx);
+        let ui = main.get_node_as::<UserInterface>("UserInterface");
+        check_eq!(ui.bind().score(), 0);
+
+        ctx.wait_until(3.0, "a mob to spawn", || !mobs(&ctx).is_empty())
+            .await?;
+
+        // Same call as when the player lands on the mob.
+        let mut mob = mobs(&ctx).remove(0);
+        mob.bind_mut().squash();
+
+        check_eq!(ui.bind().score(), 1);
+        check_eq!(
+            main.get_node_as::<Label>("UserInterface/ScoreLabel")
+                .get_text(),
+            // Whatever the locale of the machine running the tests.
+            GString::from(tr_format("SCORE", &[("score", &1)]).as_str())
+        );
+
+        Ok(())
+    })
+}
+
+fn chain_multiplies_squash_points(ctx: TestContext) -> TestFuture {
+    Box::pin(async move {
+        let mut main = add_main(&ctx);
+        let ui = main.get_node_as::<UserInterface>("UserInterface");
+        let mut player = main.get_node_as::<Player>("Player");
+
+        // Walkers always give in to the first stomp, unlike e.g. armoured mobs from the mob table.
+        let [mut first, mut second, mut third] = [(); 3].map(|_| {
+            main.bind_mut().spawn_mob(
+                Vector3::new(0.0, 0.0, -20.0),
+                Vector3::ZERO,
+                10.0,
+                MobKind::Walker,
+                1.0,
+            )
+        });
+
+        // Same calls as when the player bounces off a mob, lands on its flat top, and bounces off another one.
+        player.bind_mut().stomp(&mut first);
+        player.bind_mut().land(true);
+        player.bind_mut().stomp(&mut second);
+        check_eq!(ui.bind().score(), 1 + 2);
+
+        // After landing on the floor, squashes are worth a single point again.
+        player.bind_mut().land(false);
+        player.bind_mut().stomp(&mut third);
+        check_eq!(ui.bind().score(), 1 + 2 + 1);
+
+        Ok(())
+    })
+}
+
+fn armoured_mob_needs_two_stomps(ctx: TestContext) -> TestFuture {
+    Box::pin(async move {
+        let mut mob = ctx.add_scene::<Mob>("res://mob.tscn");
+        let mut rng = RunRng::new(1);
+        let archetype = MobKind::Armoured.create_archetype(&mut rng);
+        mob.bind_mut().initialize(
+            Vector3::ZERO,
+            Vector3::FORWARD,
+            10.0,
+            archetype,
+            1.0,
+            &mut rng,
+        );
+
+        check!(
+            !mob.bind_mut().stomp(),
+            "first stomp should only break the armour"
+        );
+        check!(mob.bind_mut().stomp(), "second stomp should squash the mob");
+
+        Ok(())
+    })
+}
+
+fn player_hit_shows_retry(ctx: TestContext) -> TestFuture {
+    Box::pin(async move {
+        let main = add_main(&ctx);
+        let retry = main.get_node_as::<ColorRect>("UserInterface/Retry");
+        let mob_timer = main.get_node_as::<Timer>("MobTimer");
+        check!(!retry.is_visible(), "retry screen should be hidden");
+        ctx.wait_until(3.0, "the first wave to start", || !mob_timer.is_stopped())
+            .await?;
+
+        main.get_node_as::<Player>("Player").bind_mut().die();
+
+        check!(retry.is_visible(), "retry screen should be shown");
+        check!(mob_timer.is_stopped(), "mobs should stop spawning");
+        check!(
+            !main
+                .get_node_as::<Label>("UserInterface/Retry/Summary")
+                .get_text()
+                .is_empty(),
+            "retry screen should summarize the run"
+        );
+
+        Ok(())
+    })
+}
+
+fn mobs(ctx: &TestContext) -> Vec<Gd<Mob>> {
+    ctx.tree()
+        .get_nodes_in_group("mob")
+        .iter_shared()
+        .filter_map(|node| node.try_cast::<Mob>().ok())
+        .collect()
+}
@@ -1,7 +1,14 @@
+mod abilities;
+mod achievements;
+mod itest;
 mod main_scene;
 mod mob;
+mod mob_archetype;
 mod player;
+mod run_history;
+mod run_rng;
 mod scorelabel;
+mod waves;
 use godot::prelude::*;
 
 struct SquashTheCreeps;
@@ -1,11 +1,18 @@
+use crate::achievements::{CHAIN_REACTION, EXTERMINATOR, FIRST_SQUASH, SURVIVOR};
 use crate::mob;
+use crate::mob_archetype::{MobKind, MobTable};
 use crate::player;
 use crate::player::Player;
+use crate::run_history::{RunHistory, RunRecord};
+use crate::run_rng::RunRng;
 use crate::scorelabel::UserInterface;
+use crate::waves::{WaveList, WaveSpec};
 
-use godot::classes::{ColorRect, InputEvent, PathFollow3D, Timer};
+use demo_common::achievements::{Achievement, Achievements};
+use demo_common::telemetry::{Event, Telemetry};
+use godot::classes::object::ConnectFlags;
+use godot::classes::{ColorRect, InputEvent, PathFollow3D, Time, Timer};
 use godot::prelude::*;
-use rand::Rng;
 
 #[derive(GodotClass)]
 #[class(init, base=Node)]
@@ -13,27 +20,115 @@ pub struct MainScene {
     #[export]
     mob_scene: OnEditor<Gd<PackedScene>>,
 
+    /// How often each kind of mob is spawned.
+    #[export]
+    mob_table: OnEditor<Gd<MobTable>>,
+
+    /// Seed for all spawning randomness. 0 picks a new random seed for every run.
+    #[export]
+    seed: i64,
+
+    // Re-created with the actual seed once the scene is ready.
+    #[init(val = RunRng::new(0))]
+    rng: RunRng,
+
+    /// Waves of mobs to spawn, one after another.
+    #[export]
+    waves: OnEditor<Gd<WaveList>>,
+
+    // Index of the current (or upcoming, during an intermission) wave, and how many of its mobs have spawned.
+    wave_index: usize,
+    #[init(val = WaveSpec::default())]
+    wave: WaveSpec,
+    spawned_in_wave: u32,
+
     #[init(node = "MobTimer")]
     mob_timer: OnReady<Gd<Timer>>,
 
+    #[init(node = "WaveTimer")]
+    wave_timer: OnReady<Gd<Timer>>,
+
     #[init(node = "UserInterface")]
     user_interface: OnReady<Gd<UserInterface>>,
 
+    /// Whether gameplay events are logged to `user://squash_telemetry.jsonl`, for analysing game balance. Can also be turned on with
+    /// `--telemetry` on the command line.
+    #[export]
+    record_telemetry: bool,
+
+    // Every retry reloads the scene, so a telemetry session covers exactly one run.
+    #[init(val = Telemetry::disabled())]
+    telemetry: Telemetry,
+    run_start_msec: u64,
+
+    /// Whether unlocked achievements and the run history are saved to `user://`. The gameplay tests turn this off, to leave the player's
+    /// progress alone.
+    #[export]
+    #[init(val = true)]
+    persist_progress: bool,
+
+    // Loaded in ready(), like the run history, depending on `persist_progress`.
+    #[init(val = OnReady::manual())]
+    achievements: OnReady<Achievements>,
+
+    #[init(val = OnReady::manual())]
+    run_history: OnReady<RunHistory>,
+
     base: Base<Node>,
 }
 
 #[godot_api]
 impl INode for MainScene {
     fn ready(&mut self) {
+        // Every retry reloads the scene, so each run starts here.
+        self.rng = RunRng::new(self.seed);
+        godot_print!("Starting run with seed {}", self.rng.seed());
+
+        if self.record_telemetry || Telemetry::requested_on_command_line() {
+            self.telemetry = Telemetry::start("squash_telemetry.jsonl");
+        }
+        self.run_start_msec = Time::singleton().get_ticks_msec();
+
+        let (achievements, run_history) = if self.persist_progress {
+            (
+                Achievements::load("squash_achievements.cfg"),
+                RunHistory::load(),
+            )
+        } else {
+            (Achievements::in_memory(), RunHistory::in_memory())
+        };
+        self.achievements.init(achievements);
+        self.run_history.init(run_history);
+        self.telemetry
+            .log(Event::new("run_start").with_int("seed", self.rng.seed()));
+
         self.base()
             .get_node_as::<ColorRect>("UserInterface/Retry")
             .hide();
 
-        self.base()
-            .get_node_as::<Player>("Player")
+        let player = self.base().get_node_as::<Player>("Player");
+        player
             .signals()
             .hit()
             .connect_other(&self.to_gd(), Self::on_player_hit);
+        player
+            .signals()
+            .bounced()
+            .connect_other(&self.to_gd(), Self::on_player_bounced);
+        player
+            .signals()
+            .bounced()
+            .connect_other(&*self.user_interface, UserInterface::on_player_bounced);
+        player
+            .signals()
+            .chain_ended()
+            .connect_other(&*self.user_interface, UserInterface::on_chain_ended);
+
+        self.wave_timer
+            .signals()
+            .timeout()
+            .connect_other(&self.to_gd(), Self::on_wave_timer_timeout);
+        self.start_intermission();
     }
 
     fn unhandled_input(&mut self, event: Gd<InputEvent>) {
@@ -50,16 +145,50 @@ impl INode for MainScene {
 
 #[godot_api]
 impl MainScene {
+    // Waits for the delay of the upcoming wave, so that the player gets a breather between waves.
+    fn start_intermission(&mut self) {
+        self.wave = self.waves.bind().wave(self.wave_index);
+        self.spawned_in_wave = 0;
+
+        let wave_number = self.wave_index + 1;
+        self.user_interface
+            .bind_mut()
+            .show_intermission(wave_number);
+
+        self.wave_timer.set_wait_time(self.wave.delay);
+        self.wave_timer.start();
+    }
+
+    // No #[func], connected in pure Rust.
+    fn on_wave_timer_timeout(&mut self) {
+        let wave_number = self.wave_index + 1;
+        self.telemetry.log(
+            Event::new("wave_start")
+                .with_int("wave", wave_number as i64)
+                .with_int("mobs", self.wave.mob_count as i64),
+        );
+        self.user_interface.bind_mut().show_wave(wave_number);
+
+        self.mob_timer.set_wait_time(self.wave.spawn_interval);
+        self.mob_timer.start();
+    }
+
     #[func]
     fn on_mob_timer_timeout(&mut self) {
-        // Create mob instance.
+        // Checked on every spawn; spawning stops for good once the player is hit.
+        let elapsed_msec = Time::singleton()
+            .get_ticks_msec()
+            .saturating_sub(self.run_start_msec);
+        if elapsed_msec >= 60_000 {
+            self.unlock(&SURVIVOR);
+        }
+
         let mut mob_spawn_location = self
             .base()
             .get_node_as::<PathFollow3D>("SpawnPath/SpawnLocation");
 
-        // Choose a random location on the SpawnPath.
-        // Set random progress using proper rng.
-        mob_spawn_location.set_progress_ratio(rand::rng().random_range(0.0..=1.0));
+        // Choose a random location on the part of the SpawnPath used by this wave.
+        mob_spawn_location.set_progress_ratio(self.wave.pick_progress(&mut self.rng));
 
         // Communicate the spawn location and the player's location to the mob.
         let player_position = self
@@ -67,10 +196,71 @@ impl MainScene {
             .get_node_as::<player::Player>("Player")
             .get_position();
 
+        let speed = self.wave.pick_speed(&mut self.rng);
+        let kind = self.mob_table.bind().pick(&mut self.rng);
+        self.spawn_mob(
+            mob_spawn_location.get_position(),
+            player_position,
+            speed,
+            kind,
+            1.0,
+        );
+
+        self.spawned_in_wave += 1;
+        if self.spawned_in_wave >= self.wave.mob_count {
+            self.mob_timer.stop();
+            self.wave_index += 1;
+            self.start_intermission();
+        }
+    }
+
+    // No #[func], connected in pure Rust.
+    fn on_mob_split_requested(&mut self, position: Vector3, velocity: Vector3) {
+        // Fragments fan out from the splitter's heading, and are a bit faster than it.
+        const FRAGMENT_ANGLES: [f32; 2] = [-0.6, 0.6];
+        const FRAGMENT_SIZE: f32 = 0.6;
+
+        // Either timer runs at any time during a run; a split that arrives after the player was hit would clutter the retry screen.
+        if self.mob_timer.is_stopped() && self.wave_timer.is_stopped() {
+            return;
+        }
+
+        let speed = velocity.length() * 1.2;
+        for angle in FRAGMENT_ANGLES {
+            let heading = velocity.rotated(Vector3::UP, angle);
+            self.spawn_mob(
+                position,
+                position + heading,
+                speed,
+                MobKind::Walker,
+                FRAGMENT_SIZE,
+            );
+        }
+    }
+
+    // Spawns a mob at `position`, walking towards `target` (give or take). Also used by the gameplay tests.
+    pub(crate) fn spawn_mob(
+        &mut self,
+        position: Vector3,
+        target: Vector3,
+        speed: f32,
+        kind: MobKind,
+        size: f32,
+    ) -> Gd<mob::Mob> {
         let mut mob = self.mob_scene.instantiate_as::<mob::Mob>();
+        let archetype = kind.create_archetype(&mut self.rng);
 
         mob.bind_mut()
-            .initialize(mob_spawn_location.get_position(), player_position);
+            .initialize(position, target, speed, archetype, size, &mut self.rng);
+
+        self.telemetry.log(
+            Event::new("spawn")
+                .with_str("kind", &format!("{kind:?}"))
+                .with_float("x", position.x as f64)
+                .with_float("z", position.z as f64)
+                .with_float("speed", speed as f64)
+                .with_float("size", size as f64),
+        );
 
         // Spawn the mob by adding it to the Main scene.
         self.base_mut().add_child(&mob);
@@ -79,11 +269,77 @@ impl MainScene {
         mob.signals()
             .squashed()
             .connect_other(&*self.user_interface, UserInterface::on_mob_squashed);
+
+        // Connected after the score label, so that the score already includes this mob.
+        mob.signals()
+            .squashed()
+            .connect_other(&self.to_gd(), Self::on_mob_squashed);
+
+        // Deferred, because mobs are squashed during the player's physics callback, where the tree should not be changed.
+        mob.signals()
+            .split_requested()
+            .builder()
+            .flags(ConnectFlags::DEFERRED)
+            .connect_other_mut(&self.to_gd(), Self::on_mob_split_requested);
+
+        mob
+    }
+
+    // No #[func], connected in pure Rust.
+    fn on_mob_squashed(&mut self) {
+        let score = self.user_interface.bind().score();
+        self.telemetry
+            .log(Event::new("squash").with_int("score", score as i64));
+
+        self.unlock(&FIRST_SQUASH);
+        if score >= 25 {
+            self.unlock(&EXTERMINATOR);
+        }
+    }
+
+    // No #[func], connected in pure Rust.
+    fn on_player_bounced(&mut self, chain: u32) {
+        if chain >= 5 {
+            self.unlock(&CHAIN_REACTION);
+        }
+    }
+
+    // Celebrates an achievement the first time it is reached.
+    fn unlock(&mut self, achievement: &Achievement) {
+        if !self.achievements.unlock(achievement) {
+            return;
+        }
+
+        self.telemetry
+            .log(Event::new("achievement").with_str("id", achievement.id));
+        self.user_interface.bind_mut().show_achievement(achievement);
     }
 
     #[func]
     pub fn on_player_hit(&mut self) {
         self.mob_timer.stop();
+        self.wave_timer.stop();
+
+        let elapsed_msec = Time::singleton()
+            .get_ticks_msec()
+            .saturating_sub(self.run_start_msec);
+        let score = self.user_interface.bind().score();
+
+        self.telemetry.log(Event::new("hit"));
+        self.telemetry.log(
+            Event::new("run_end")
+                .with_int("score", score as i64)
+                .with_float("duration", elapsed_msec as f64 / 1000.0),
+        );
+
+        let run = RunRecord::new(score, elapsed_msec as f64 / 1000.0);
+        let new_best = self.run_history.record(run);
+        self.user_interface.bind_mut().show_run_summary(
+            score,
+            new_best,
+            self.run_history.best(),
+            self.run_history.recent(),
+        );
 
         self.base()
             .get_node_as::<ColorRect>("UserInterface/Retry")
@@ -1,41 +1,75 @@
-use godot::classes::{AnimationPlayer, CharacterBody3D, ICharacterBody3D};
+use crate::mob_archetype::{MobArchetype, MobContext, Walker};
+use crate::run_rng::RunRng;
+use godot::classes::base_material_3d::{ShadingMode, Transparency};
+use godot::classes::{
+    AnimationPlayer, CharacterBody3D, ICharacterBody3D, MeshInstance3D, StandardMaterial3D,
+};
 use godot::prelude::*;
 use rand::Rng;
 use std::f32::consts::PI;
 
 #[derive(GodotClass)]
-#[class(init, base=CharacterBody3D)]
+#[class(base=CharacterBody3D)]
 pub struct Mob {
-    // Minimum speed of the mob in meters per second.
-    #[export]
-    min_speed: f32,
-    // Maximum speed of the mob in meters per second.
-    #[export]
-    max_speed: f32,
+    archetype: Box<dyn MobArchetype>,
+    // Mobs don't collide with anything (see mob.tscn), so jumping ones land on this height by hand.
+    ground_height: f32,
     base: Base<CharacterBody3D>,
 }
 
 #[godot_api]
 impl ICharacterBody3D for Mob {
-    fn physics_process(&mut self, _delta: f64) {
+    fn init(base: Base<CharacterBody3D>) -> Self {
+        Self {
+            archetype: Box::new(Walker),
+            ground_height: 0.0,
+            base,
+        }
+    }
+
+    fn physics_process(&mut self, delta: f64) {
+        let ctx = self.context();
+        let velocity = self.archetype.update(&ctx, delta);
+
+        // Keep facing the direction of movement, e.g. when a chaser changes course.
+        let heading = Vector3::new(velocity.x, 0.0, velocity.z);
+        if heading.length_squared() > 0.01
+            && heading != Vector3::new(ctx.velocity.x, 0.0, ctx.velocity.z)
+        {
+            self.base_mut().look_at(ctx.position + heading);
+        }
+
+        self.base_mut().set_velocity(velocity);
         self.base_mut().move_and_slide();
+
+        let mut position = self.base().get_position();
+        if position.y < self.ground_height {
+            position.y = self.ground_height;
+            self.base_mut().set_position(position);
+        }
     }
 }
 #[godot_api]
 impl Mob {
-    #[func]
-    pub fn initialize(&mut self, start_position: Vector3, player_position: Vector3) {
+    // No #[func]: the RNG and archetype parameters cannot be passed from GDScript.
+    // `speed` is in meters per second; the current wave decides it. `size` scales model and collision shape; 1 is a regular mob.
+    pub fn initialize(
+        &mut self,
+        start_position: Vector3,
+        player_position: Vector3,
+        speed: f32,
+        archetype: Box<dyn MobArchetype>,
+        size: f32,
+        rng: &mut RunRng,
+    ) {
         self.base_mut()
             .look_at_from_position(start_position, player_position);
 
         self.base_mut()
-            .rotate_y(rand::rng().random_range(-PI / 4.0..PI / 4.0));
-
-        let random_speed = rand::rng().random_range(self.min_speed..self.max_speed);
+            .rotate_y(rng.random_range(-PI / 4.0..PI / 4.0));
 
         // We calculate a forward velocity first, which represents the speed.
-        self.base_mut()
-            .set_velocity(Vector3::FORWARD * random_speed);
+        self.base_mut().set_velocity(Vector3::FORWARD * speed);
 
         let rotation = self.base().get_rotation();
         let velocity = self.base().get_velocity();
@@ -44,21 +78,51 @@ impl Mob {
         self.base_mut()
             .set_velocity(velocity.rotated(Vector3::UP, rotation.y));
 
-        let animation_speed = rand::rng().random_range(1.0..6.0);
+        let animation_speed = rng.random_range(1.0..6.0);
 
         self.base()
             .get_node_as::<AnimationPlayer>("AnimationPlayer")
-            .set_speed_scale(animation_speed as f32)
+            .set_speed_scale(animation_speed as f32);
+
+        // Physics bodies themselves should not be scaled, but their shapes can.
+        let scale = Vector3::splat(size);
+        for path in ["Pivot", "CollisionShape3D", "VisibleOnScreenNotifier3D"] {
+            self.base().get_node_as::<Node3D>(path).set_scale(scale);
+        }
+
+        self.ground_height = start_position.y;
+        self.archetype = archetype;
+        self.apply_tint();
     }
 
     // Emitted when the player jumped on the mob.
     #[signal]
     pub fn squashed();
 
+    // Emitted when a mob that splits is squashed, so that `MainScene` spawns the smaller mobs.
+    #[signal]
+    pub fn split_requested(position: Vector3, velocity: Vector3);
+
+    /// Called when the player lands on the mob. Returns whether it should be squashed; otherwise the player only bounces off.
+    pub fn stomp(&mut self) -> bool {
+        let ctx = self.context();
+        let squashable = self.archetype.stomp(&ctx);
+
+        // E.g. armour may have broken.
+        self.apply_tint();
+        squashable
+    }
+
     #[func]
     pub fn squash(&mut self) {
         self.signals().squashed().emit();
 
+        if self.archetype.splits() {
+            let position = self.base().get_position();
+            let velocity = self.base().get_velocity();
+            self.signals().split_requested().emit(position, velocity);
+        }
+
         self.base_mut().queue_free();
     }
 
@@ -66,4 +130,60 @@ impl Mob {
     fn on_visible_on_screen_notifier_3d_screen_exited(&mut self) {
         self.base_mut().queue_free();
     }
+
+    fn context(&self) -> MobContext {
+        let position = self.base().get_position();
+
+        MobContext {
+            position,
+            velocity: self.base().get_velocity(),
+            height: position.y - self.ground_height,
+            target: if self.archetype.needs_target() {
+                self.player_position()
+            } else {
+                None
+            },
+        }
+    }
+
+    fn player_position(&self) -> Option<Vector3> {
+        if !self.base().is_inside_tree() {
+            return None;
+        }
+
+        self.base()
+            .get_tree()
+            .get_first_node_in_group("player")
+            .and_then(|node| node.try_cast::<Node3D>().ok())
+            .map(|player| player.get_position())
+    }
+
+    // Tints the model by drawing a translucent, unshaded layer over all of its meshes.
+    fn apply_tint(&mut self) {
+        let tint = self.archetype.tint();
+
+        let overlay = (tint != Color::WHITE).then(|| {
+            let mut material = StandardMaterial3D::new_gd();
+            material.set_shading_mode(ShadingMode::UNSHADED);
+            material.set_transparency(Transparency::ALPHA);
+            material.set_albedo(Color { a: 0.5, ..tint });
+            material
+        });
+
+        let meshes = self
+            .base()
+            .get_node_as::<Node3D>("Pivot")
+            .find_children_ex("*")
+            .type_("MeshInstance3D")
+            .owned(false)
+            .done();
+
+        for mesh in meshes.iter_shared() {
+            let mut mesh = mesh.cast::<MeshInstance3D>();
+            match &overlay {
+                Some(material) => mesh.set_material_overlay(material),
+                None => mesh.set_material_overlay(Gd::null_arg()),
+            }
+        }
+    }
 }
@@ -0,0 +1,280 @@
+use godot::prelude::*;
+
+use rand::Rng;
+
+// Below this height, jumping mobs count as standing on the ground.
+const AIRBORNE_HEIGHT: f32 = 0.1;
+
+/// What an archetype knows about its mob and the world, in one physics frame.
+pub struct MobContext {
+    pub position: Vector3,
+    pub velocity: Vector3,
+    /// Height above the ground the mob was spawned on.
+    pub height: f32,
+    /// Position of the player, unless they were caught already. Only looked up for archetypes that [need it](MobArchetype::needs_target).
+    pub target: Option<Vector3>,
+}
+
+impl MobContext {
+    pub fn is_airborne(&self) -> bool {
+        self.height > AIRBORNE_HEIGHT
+    }
+}
+
+/// Strategy that decides how a mob moves and how it reacts to being stomped. Every mob owns one, chosen when it is spawned.
+///
+/// To add a new archetype, implement this trait, add a variant to [`MobKind`] and list it in the mob table resource.
+pub trait MobArchetype {
+    /// Called every physics frame. Returns the velocity for this frame.
+    fn update(&mut self, ctx: &MobContext, delta: f64) -> Vector3;
+
+    /// Called when the player lands on the mob. Returns whether the mob is squashed; otherwise, the player just bounces off.
+    fn stomp(&mut self, _ctx: &MobContext) -> bool {
+        true
+    }
+
+    /// Whether [`MobContext::target`] should be filled in. Finding the player costs a tree lookup per mob and frame, so only
+    /// archetypes that steer towards them ask for it.
+    fn needs_target(&self) -> bool {
+        false
+    }
+
+    /// Whether the mob breaks into smaller mobs once squashed.
+    fn splits(&self) -> bool {
+        false
+    }
+
+    /// Tint of the model, so players can tell archetypes apart. May change over the mob's life.
+    fn tint(&self) -> Color {
+        Color::WHITE
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// Walks in a straight line, like the mobs of the original game.
+pub struct Walker;
+
+impl MobArchetype for Walker {
+    fn update(&mut self, ctx: &MobContext, _delta: f64) -> Vector3 {
+        ctx.velocity
+    }
+}
+
+/// Walks in a straight line, and needs to be stomped twice: the first stomp only breaks its armour.
+pub struct Armoured {
+    /// Stomps left until the mob can be squashed.
+    armour: u32,
+}
+
+impl MobArchetype for Armoured {
+    fn update(&mut self, ctx: &MobContext, _delta: f64) -> Vector3 {
+        ctx.velocity
+    }
+
+    fn stomp(&mut self, _ctx: &MobContext) -> bool {
+        if self.armour == 0 {
+            return true;
+        }
+
+        self.armour -= 1;
+        false
+    }
+
+    fn tint(&self) -> Color {
+        if self.armour > 0 {
+            Color::from_rgb(0.45, 0.5, 0.6)
+        } else {
+            Color::WHITE
+        }
+    }
+}
+
+/// Hops along its way. It cannot be squashed in mid-air, so players have to time their jump.
+pub struct Jumper {
+    /// Seconds on the ground between two jumps.
+    interval: f64,
+    /// Seconds until the next jump, while on the ground.
+    cooldown: f64,
+    /// Vertical speed at takeoff, in meters per second.
+    jump_speed: f32,
+    /// Downward acceleration, in meters per second squared.
+    gravity: f32,
+    vertical_speed: f32,
+}
+
+impl MobArchetype for Jumper {
+    fn update(&mut self, ctx: &MobContext, delta: f64) -> Vector3 {
+        if ctx.is_airborne() || self.vertical_speed > 0.0 {
+            self.vertical_speed -= self.gravity * delta as f32;
+        } else {
+            self.vertical_speed = 0.0;
+            self.cooldown -= delta;
+
+            if self.cooldown <= 0.0 {
+                self.cooldown = self.interval;
+                self.vertical_speed = self.jump_speed;
+            }
+        }
+
+        Vector3::new(ctx.velocity.x, self.vertical_speed, ctx.velocity.z)
+    }
+
+    fn stomp(&mut self, ctx: &MobContext) -> bool {
+        !ctx.is_airborne()
+    }
+
+    fn tint(&self) -> Color {
+        Color::from_rgb(0.55, 1.0, 0.6)
+    }
+}
+
+/// Turns towards the player at regular intervals, keeping its speed.
+pub struct Chaser {
+    /// Seconds between two course corrections.
+    interval: f64,
+    /// Seconds until the next course correction.
+    cooldown: f64,
+}
+
+impl MobArchetype for Chaser {
+    fn update(&mut self, ctx: &MobContext, delta: f64) -> Vector3 {
+        self.cooldown -= delta;
+
+        let Some(target) = ctx.target.filter(|_| self.cooldown <= 0.0) else {
+            return ctx.velocity;
+        };
+        self.cooldown += self.interval;
+
+        let mut direction = target - ctx.position;
+        direction.y = 0.0;
+        if direction.length_squared() < 0.01 {
+            return ctx.velocity;
+        }
+
+        let speed = Vector3::new(ctx.velocity.x, 0.0, ctx.velocity.z).length();
+        direction.normalized() * speed
+    }
+
+    fn needs_target(&self) -> bool {
+        true
+    }
+
+    fn tint(&self) -> Color {
+        Color::from_rgb(1.0, 0.55, 0.55)
+    }
+}
+
+/// Walks in a straight line, and breaks into smaller mobs when squashed.
+pub struct Splitter;
+
+impl MobArchetype for Splitter {
+    fn update(&mut self, ctx: &MobContext, _delta: f64) -> Vector3 {
+        ctx.velocity
+    }
+
+    fn splits(&self) -> bool {
+        true
+    }
+
+    fn tint(&self) -> Color {
+        Color::from_rgb(0.7, 0.7, 1.0)
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+#[derive(GodotConvert, Var, Export, Default, Copy, Clone, Debug, PartialEq, Eq)]
+#[godot(via = i64)]
+pub enum MobKind {
+    #[default]
+    Walker,
+    Armoured,
+    Jumper,
+    Chaser,
+    Splitter,
+}
+
+impl MobKind {
+    /// Creates the archetype for a newly spawned mob. Its parameters vary a bit from mob to mob.
+    pub fn create_archetype(self, rng: &mut impl Rng) -> Box<dyn MobArchetype> {
+        match self {
+            Self::Walker => Box::new(Walker),
+            Self::Armoured => Box::new(Armoured { armour: 1 }),
+            Self::Jumper => Box::new(Jumper {
+                interval: rng.random_range(0.6..1.2),
+                cooldown: rng.random_range(0.0..0.6),
+                jump_speed: rng.random_range(6.0..8.0),
+                gravity: 20.0,
+                vertical_speed: 0.0,
+            }),
+            Self::Chaser => Box::new(Chaser {
+                interval: rng.random_range(1.0..1.6),
+                // Lets the chaser walk into the arena before it starts to steer.
+                cooldown: 1.0,
+            }),
+            Self::Splitter => Box::new(Splitter),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// How likely a kind of mob is to be spawned, relative to the other entries of a [`MobTable`].
+#[derive(GodotClass)]
+#[class(init, base=Resource)]
+pub struct MobTableEntry {
+    #[export]
+    kind: MobKind,
+
+    /// Relative weight; entries with a weight of 0 are never picked.
+    #[export]
+    #[init(val = 1.0)]
+    weight: f32,
+
+    base: Base<Resource>,
+}
+
+/// Weighted table from which the kind of each spawned mob is drawn.
+#[derive(GodotClass)]
+#[class(init, base=Resource)]
+pub struct MobTable {
+    /// Empty slots are ignored, like entries with a weight of 0.
+    #[export]
+    entries: Array<Option<Gd<MobTableEntry>>>,
+
+    base: Base<Resource>,
+}
+
+impl MobTable {
+    /// Draws a kind according to the weights. An empty table only spawns walkers.
+    pub fn pick(&self, rng: &mut impl Rng) -> MobKind {
+        let entries: Vec<(MobKind, f32)> = self
+            .entries
+            .iter_shared()
+            .flatten()
+            .map(|entry| {
+                let entry = entry.bind();
+                (entry.kind, entry.weight)
+            })
+            .filter(|(_, weight)| *weight > 0.0)
+            .collect();
+
+        if entries.is_empty() {
+            return MobKind::Walker;
+        }
+
+        let total: f32 = entries.iter().map(|(_, weight)| weight).sum();
+
+        let mut roll = rng.random_range(0.0..total);
+        for (kind, weight) in &entries {
+            if roll < *weight {
+                return *kind;
+            }
+            roll -= weight;
+        }
+
+        // Only reachable through rounding errors.
+        entries.last().map_or(MobKind::Walker, |(kind, _)| *kind)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::abilities::{Ability, AirDash, DoubleJump, GroundPound, Impact, Motion};
 use crate::mob::Mob;
 use godot::classes::{AnimationPlayer, CharacterBody3D, CollisionShape3D, ICharacterBody3D, Input};
 use godot::prelude::*;
@@ -25,10 +26,86 @@ pub struct Player {
     /// The target velocity of the character (node property)
     #[export]
     target_velocity: Vector3,
+
+    /// Seconds after walking off a ledge during which the player can still jump.
+    #[export]
+    #[init(val = 0.1)]
+    coyote_time: f64,
+
+    /// Seconds a jump press is remembered before landing, so that jumping slightly too early still works.
+    #[export]
+    #[init(val = 0.12)]
+    jump_buffer_time: f64,
+
+    /// Vertical impulse of jumps in mid-air, in meters per second.
+    #[export]
+    #[init(val = 16.0)]
+    double_jump_impulse: f32,
+
+    /// Jumps in mid-air until landing again; 0 disables the double jump.
+    #[export]
+    #[init(val = 1)]
+    air_jumps: u32,
+
+    /// Speed of the air dash, in meters per second.
+    #[export]
+    #[init(val = 30.0)]
+    dash_speed: f32,
+
+    /// Seconds an air dash lasts.
+    #[export]
+    #[init(val = 0.15)]
+    dash_duration: f64,
+
+    /// Seconds after an air dash until the next one.
+    #[export]
+    #[init(val = 0.8)]
+    dash_cooldown: f64,
+
+    /// Downward speed of the ground pound, in meters per second.
+    #[export]
+    #[init(val = 40.0)]
+    ground_pound_speed: f32,
+
+    /// Meters around the landing spot of a ground pound in which mobs are squashed.
+    #[export]
+    #[init(val = 3.0)]
+    ground_pound_radius: f32,
+
+    /// Seconds after a ground pound until the next one.
+    #[export]
+    #[init(val = 1.5)]
+    ground_pound_cooldown: f64,
+
+    // Created from the exports above once the player is ready.
+    abilities: Vec<Box<dyn Ability>>,
+    // Seconds left to jump after leaving the floor, and to act on an early jump press.
+    coyote_left: f64,
+    jump_buffer_left: f64,
+
+    // Mobs squashed since the player last stood on the floor.
+    chain: u32,
+
     base: Base<CharacterBody3D>,
 }
 #[godot_api]
 impl ICharacterBody3D for Player {
+    fn ready(&mut self) {
+        self.abilities = vec![
+            Box::new(DoubleJump::new(self.double_jump_impulse, self.air_jumps)),
+            Box::new(AirDash::new(
+                self.dash_speed,
+                self.dash_duration,
+                self.dash_cooldown,
+            )),
+            Box::new(GroundPound::new(
+                self.ground_pound_speed,
+                self.ground_pound_radius,
+                self.ground_pound_cooldown,
+            )),
+        ];
+    }
+
     fn physics_process(&mut self, delta: f64) {
         let mut direction = Vector3::ZERO;
 
@@ -68,16 +145,56 @@ impl ICharacterBody3D for Player {
         self.target_velocity.x = direction.x * self.speed;
         self.target_velocity.z = direction.z * self.speed;
 
-        // jumping.
-        if self.base().is_on_floor() && input.is_action_just_pressed("jump") {
+        // jumping, a bit lenient on timing: right after walking off a ledge, or right before landing also works.
+        let on_floor = self.base().is_on_floor();
+        if on_floor {
+            self.coyote_left = self.coyote_time;
+        } else {
+            self.coyote_left -= delta;
+        }
+
+        if input.is_action_just_pressed("jump") {
+            self.jump_buffer_left = self.jump_buffer_time;
+        }
+
+        let jumped = self.jump_buffer_left > 0.0 && self.coyote_left > 0.0;
+        if jumped {
             self.target_velocity.y = self.jump_impulse;
+            self.jump_buffer_left = 0.0;
+            self.coyote_left = 0.0;
+        } else {
+            self.jump_buffer_left -= delta;
         }
+
+        // The model faces along the pivot's Z axis, see above.
+        let facing = self
+            .base()
+            .get_node_as::<Node3D>("Pivot")
+            .get_basis()
+            .col_c();
+        let mut motion = Motion {
+            velocity: self.target_velocity,
+            on_floor,
+            jumped,
+            direction,
+            facing: Vector3::new(facing.x, 0.0, facing.z).normalized(),
+            gravity: !on_floor,
+        };
+        let impacts = self.update_abilities(&mut motion, delta);
+        self.target_velocity = motion.velocity;
+
         // We apply gravity every frame so the character always collides with the ground when moving.
         // This is necessary for the is_on_floor() function to work as a body can always detect
         // the floor, walls, etc. when a collision happens the same frame.
-        if !self.base().is_on_floor() {
+        if motion.gravity {
             self.target_velocity.y -= self.fall_acceleration * delta as f32;
         }
+
+        for impact in impacts {
+            match impact {
+                Impact::Shockwave { radius } => self.shockwave(radius),
+            }
+        }
         // moving the Character
         let velocity = self.target_velocity;
         self.base_mut().set_velocity(velocity);
@@ -88,6 +205,7 @@ impl ICharacterBody3D for Player {
         // smooth out the character's motion. So we have to loop over all collisions that may have
         // happened.
         // If there are no "slides" this frame, the loop below won't run.
+        let mut landed_on_mob = false;
         for index in 0..self.base().get_slide_collision_count() {
             let collision = self.base_mut().get_slide_collision(index).unwrap();
             // Skip given collider if they are not a Mob.
@@ -101,13 +219,19 @@ impl ICharacterBody3D for Player {
                 continue;
             };
             if Vector3::UP.dot(collision.get_normal()) > 0.1 {
-                mob.bind_mut().squash();
+                landed_on_mob = true;
                 self.target_velocity.y = self.bounce_impulse;
+                self.stomp(&mut mob);
                 // Prevent this block from running more than once,
                 // which would award the player more than 1 point for squashing a single mob.
                 break;
             }
         }
+
+        if self.base().is_on_floor() {
+            self.land(landed_on_mob);
+        }
+
         // This makes the character follow a nice arc when jumping.
         let mut pivot = self.base().get_node_as::<Node3D>("Pivot");
         let mut pivot_rotation = pivot.get_rotation();
@@ -116,11 +240,94 @@ impl ICharacterBody3D for Player {
     }
 }
 
+impl Player {
+    // Triggers the abilities whose action was just pressed, then updates all of them.
+    fn update_abilities(&mut self, motion: &mut Motion, delta: f64) -> Vec<Impact> {
+        let input = Input::singleton();
+
+        let mut impacts = Vec::new();
+        for ability in &mut self.abilities {
+            let used = input.is_action_just_pressed(ability.action()) && ability.trigger(motion);
+
+            // E.g. a double jump uses up the press; it should not trigger another jump upon landing.
+            if used && ability.consumes_jump_press() {
+                self.jump_buffer_left = 0.0;
+            }
+
+            impacts.extend(ability.update(motion, delta));
+        }
+
+        impacts
+    }
+
+    // Some mobs survive a stomp, e.g. armoured ones; bouncing off them does not extend the chain.
+    // Also driven by the gameplay tests.
+    pub(crate) fn stomp(&mut self, mob: &mut Gd<Mob>) {
+        if !mob.bind_mut().stomp() {
+            return;
+        }
+
+        // Announce the chain before squashing, so that the mob's points are already multiplied.
+        self.chain += 1;
+        let chain = self.chain;
+        self.signals().bounced().emit(chain);
+
+        mob.bind_mut().squash();
+    }
+
+    // Called when standing on the floor after moving. The flat top of a mob counts as floor too, so landing on the mob just bounced
+    // off does not end the chain. Also driven by the gameplay tests.
+    pub(crate) fn land(&mut self, on_mob: bool) {
+        if on_mob || self.chain == 0 {
+            return;
+        }
+
+        let chain = self.chain;
+        self.chain = 0;
+        self.signals().chain_ended().emit(chain);
+    }
+
+    // Stomps all mobs on the ground within `radius`. They are worth a single point each, since only squashes in the air make up a chain.
+    fn shockwave(&mut self, radius: f32) {
+        // The pound has landed, which ends any chain leading up to it.
+        self.land(false);
+
+        let position = self.base().get_position();
+
+        let mut mobs: Vec<Gd<Mob>> = self
+            .base()
+            .get_tree()
+            .get_nodes_in_group("mob")
+            .iter_shared()
+            .filter(|node| !node.is_queued_for_deletion())
+            .filter_map(|node| node.try_cast::<Mob>().ok())
+            .filter(|mob| {
+                let offset = mob.get_position() - position;
+                Vector2::new(offset.x, offset.z).length() <= radius
+            })
+            .collect();
+
+        for mob in &mut mobs {
+            if mob.bind_mut().stomp() {
+                mob.bind_mut().squash();
+            }
+        }
+    }
+}
+
 #[godot_api]
 impl Player {
     #[signal]
     pub fn hit();
 
+    // Emitted when bouncing off a squashed mob. `chain` counts the mobs squashed without touching the floor, including this one.
+    #[signal]
+    pub fn bounced(chain: u32);
+
+    // Emitted when landing on the floor after squashing at least one mob. `chain` is the number of mobs squashed in the air.
+    #[signal]
+    pub fn chain_ended(chain: u32);
+
     #[func]
     pub fn die(&mut self) {
         self.signals().hit().emit();
@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+
+use godot::classes::{ConfigFile, FileAccess, Time};
+use godot::global::Error;
+use godot::prelude::*;
+
+const SAVE_PATH: &str = "user://squash_runs.cfg";
+
+// Bump this whenever the layout of the save file changes. Files with a different version are discarded.
+const SAVE_VERSION: i64 = 1;
+
+// How many of the latest runs are kept.
+const CAPACITY: usize = 5;
+
+/// A single finished run.
+#[derive(Clone, Debug)]
+pub struct RunRecord {
+    pub score: u32,
+    /// Date of the run, formatted as `YYYY-MM-DD`.
+    pub date: GString,
+    /// Time survived, in seconds.
+    pub duration: f64,
+}
+
+impl RunRecord {
+    /// Creates a record for a run that ended just now.
+    pub fn new(score: u32, duration: f64) -> Self {
+        Self {
+            score,
+            date: Time::singleton().get_date_string_from_system(),
+            duration,
+        }
+    }
+}
+
+/// Best score and latest runs, persisted to `user://` between sessions.
+///
+/// Every retry reloads the main scene, which drops all state of the previous run; this is why the history is read from disk in each
+/// run, and written as soon as a run ends.
+#[derive(Default)]
+pub struct RunHistory {
+    best: u32,
+    /// Latest run first.
+    recent: VecDeque<RunRecord>,
+    /// Whether the history is saved to disk; see [`in_memory()`](Self::in_memory).
+    persistent: bool,
+}
+
+impl RunHistory {
+    /// Loads the history from disk. A missing or outdated file results in an empty history.
+    pub fn load() -> Self {
+        let empty = Self {
+            persistent: true,
+            ..Self::default()
+        };
+
+        if !FileAccess::file_exists(SAVE_PATH) {
+            return empty;
+        }
+
+        let mut config = ConfigFile::new_gd();
+        if config.load(SAVE_PATH) != Error::OK {
+            godot_warn!("Could not read run history from {SAVE_PATH}, starting over.");
+            return empty;
+        }
+
+        let version = config.get_value("meta", "version").try_to::<i64>().ok();
+        if version != Some(SAVE_VERSION) {
+            godot_warn!("Run history file {SAVE_PATH} has an unsupported version, discarding it.");
+            return empty;
+        }
+
+        let best = config
+            .get_value("best", "score")
+            .try_to::<u32>()
+            .unwrap_or(0);
+
+        // Each run is stored in its own section, latest first: [run_0], [run_1], ...
+        let mut recent = VecDeque::new();
+        for i in 0..CAPACITY {
+            let section = format!("run_{i}");
+            if !config.has_section(section.as_str()) {
+                break;
+            }
+
+            let score = config.get_value(section.as_str(), "score").try_to::<u32>();
+            let date = config
+                .get_value(section.as_str(), "date")
+                .try_to::<GString>();
+            let duration = config
+                .get_value(section.as_str(), "duration")
+                .try_to::<f64>();
+
+            if let (Ok(score), Ok(date), Ok(duration)) = (score, date, duration) {
+                recent.push_back(RunRecord {
+                    score,
+                    date,
+                    duration,
+                });
+            }
+        }
+
+        Self {
+            best,
+            recent,
+            persistent: true,
+        }
+    }
+
+    /// An empty history that is never saved, e.g. for tests that must not touch the player's records.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Highest score of all runs so far, including those no longer in the recent list.
+    pub fn best(&self) -> u32 {
+        self.best
+    }
+
+    /// Latest runs, most recent first.
+    pub fn recent(&self) -> &VecDeque<RunRecord> {
+        &self.recent
+    }
+
+    /// Adds a finished run and saves the history (unless in memory).
+    ///
+    /// Returns whether the run set a new best score.
+    pub fn record(&mut self, run: RunRecord) -> bool {
+        let new_best = run.score > self.best;
+        if new_best {
+            self.best = run.score;
+        }
+
+        self.recent.push_front(run);
+        self.recent.truncate(CAPACITY);
+        if self.persistent {
+            self.save();
+        }
+
+        new_best
+    }
+
+    fn save(&self) {
+        let mut config = ConfigFile::new_gd();
+        config.set_value("meta", "version", &SAVE_VERSION.to_variant());
+        config.set_value("best", "score", &self.best.to_variant());
+
+        for (i, run) in self.recent.iter().enumerate() {
+            let section = format!("run_{i}");
+            config.set_value(section.as_str(), "score", &run.score.to_variant());
+            config.set_value(section.as_str(), "date", &run.date.to_variant());
+            config.set_value(section.as_str(), "duration", &run.duration.to_variant());
+        }
+
+        if config.save(SAVE_PATH) != Error::OK {
+            godot_warn!("Could not write run history to {SAVE_PATH}.");
+        }
+    }
+}
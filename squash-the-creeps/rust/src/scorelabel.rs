@@ -1,10 +1,36 @@
-use godot::classes::{Control, Label};
+use std::collections::VecDeque;
+
+use crate::run_history::RunRecord;
+
+use demo_common::achievement_toast::AchievementToast;
+use demo_common::achievements::Achievement;
+use demo_common::locale::{tr, tr_format};
+use demo_common::settings_panel::SettingsPanel;
+use godot::classes::notify::ControlNotification;
+use godot::classes::{Control, IControl, Label};
 use godot::prelude::*;
 
 #[derive(GodotClass)]
 #[class(init, base=Control)]
 pub struct UserInterface {
     score: u32,
+
+    // Mobs squashed in the current chain, i.e. without touching the floor. Each squash is worth that many points.
+    chain: u32,
+
+    // Current wave number, counting from 1, and whether it is still about to start.
+    wave: usize,
+    wave_incoming: bool,
+
+    // Shown on the retry screen once the run is over.
+    summary: Option<RunSummary>,
+
+    #[init(node = "SettingsPanel")]
+    settings_panel: OnReady<Gd<SettingsPanel>>,
+
+    #[init(node = "AchievementToast")]
+    achievement_toast: OnReady<Gd<AchievementToast>>,
+
     base: Base<Control>,
 }
 
@@ -12,9 +38,158 @@ pub struct UserInterface {
 impl UserInterface {
     #[func]
     pub fn on_mob_squashed(&mut self) {
-        self.score += 1;
+        self.score += self.chain.max(1);
+        self.render_score();
+    }
+
+    // No #[func], connected in pure Rust.
+    pub fn on_player_bounced(&mut self, chain: u32) {
+        self.chain = chain;
+        self.render_chain();
+    }
+
+    // No #[func], connected in pure Rust.
+    pub fn on_chain_ended(&mut self, _chain: u32) {
+        self.chain = 0;
+        self.render_chain();
+    }
+
+    fn render_score(&mut self) {
+        let text = tr_format("SCORE", &[("score", &self.score)]);
 
         let mut label = self.base().get_node_as::<Label>("ScoreLabel");
-        label.set_text(format!("Score: {}", self.score).as_str());
+        label.set_text(text.as_str());
+    }
+
+    // A single squash is no chain yet, so the label only shows up from the second one on.
+    fn render_chain(&mut self) {
+        let mut label = self.base().get_node_as::<Label>("ChainLabel");
+        if self.chain < 2 {
+            label.hide();
+            return;
+        }
+
+        let text = tr_format("CHAIN", &[("chain", &self.chain)]);
+        label.set_text(text.as_str());
+        label.show();
+    }
+
+    fn render_wave(&mut self) {
+        let key = if self.wave_incoming {
+            "WAVE_INCOMING"
+        } else {
+            "WAVE"
+        };
+        let text = tr_format(key, &[("wave", &self.wave)]);
+
+        let mut label = self.base().get_node_as::<Label>("WaveLabel");
+        label.set_text(text.as_str());
+    }
+
+    /// Announces wave number `wave` (counting from 1) during the intermission before it.
+    pub fn show_intermission(&mut self, wave: usize) {
+        self.wave = wave;
+        self.wave_incoming = true;
+        self.render_wave();
+    }
+
+    /// Shows that wave number `wave` (counting from 1) is underway.
+    pub fn show_wave(&mut self, wave: usize) {
+        self.wave = wave;
+        self.wave_incoming = false;
+        self.render_wave();
     }
+
+    /// Shows the result of the finished run on the retry screen, next to the best score and the latest runs (including this one).
+    // No #[func], since Godot has no notion of RunRecord.
+    pub fn show_run_summary(
+        &mut self,
+        score: u32,
+        new_best: bool,
+        best: u32,
+        recent: &VecDeque<RunRecord>,
+    ) {
+        self.summary = Some(RunSummary {
+            score,
+            new_best,
+            best,
+            recent: recent.iter().cloned().collect(),
+        });
+        self.render_summary();
+    }
+
+    fn render_summary(&mut self) {
+        let Some(summary) = &self.summary else {
+            return;
+        };
+
+        let mut text = tr_format(
+            "RUN_RESULT",
+            &[("score", &summary.score), ("best", &summary.best)],
+        );
+        if summary.new_best {
+            text += "\n";
+            text += &tr("NEW_BEST");
+        }
+
+        text += "\n\n";
+        text += &tr("RECENT_RUNS");
+        for run in &summary.recent {
+            let seconds = run.duration as i64;
+            text += &format!(
+                "\n{}  ({}:{:02}, {})",
+                run.score,
+                seconds / 60,
+                seconds % 60,
+                run.date
+            );
+        }
+
+        let mut label = self.base().get_node_as::<Label>("Retry/Summary");
+        label.set_text(text.as_str());
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn show_achievement(&mut self, achievement: &Achievement) {
+        self.achievement_toast.bind_mut().push(achievement);
+    }
+
+    // The panel pauses the game while it is open (see main.tscn).
+    #[func]
+    fn on_settings_button_pressed(&mut self) {
+        self.settings_panel.bind_mut().open();
+    }
+}
+
+#[godot_api]
+impl IControl for UserInterface {
+    fn ready(&mut self) {
+        self.render_score();
+        self.render_chain();
+    }
+
+    // Score, chain and wave texts are assembled in code, so Godot cannot translate them by themselves.
+    fn on_notification(&mut self, what: ControlNotification) {
+        if what == ControlNotification::TRANSLATION_CHANGED && self.base().is_node_ready() {
+            self.render_score();
+            self.render_chain();
+            if self.wave > 0 {
+                self.render_wave();
+            }
+            self.render_summary();
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+// Everything the retry screen shows about the finished run.
+struct RunSummary {
+    score: u32,
+    new_best: bool,
+    best: u32,
+    recent: Vec<RunRecord>,
 }
@@ -0,0 +1,155 @@
+use godot::prelude::*;
+use rand::Rng;
+
+/// Spawn parameters of one wave, as used by the game.
+#[derive(Copy, Clone, Debug)]
+pub struct WaveSpec {
+    pub mob_count: u32,
+    /// Seconds between two mob spawns.
+    pub spawn_interval: f64,
+    /// Mob speed range, in meters per second.
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// Start and end of the part of the spawn path where mobs appear, as fractions of the whole (closed) path.
+    pub arc_start: f32,
+    pub arc_end: f32,
+    /// Seconds of intermission before the wave starts.
+    pub delay: f64,
+}
+
+impl WaveSpec {
+    /// Random position on the spawn arc, as progress ratio of the spawn path.
+    ///
+    /// The arc may wrap around the end of the path: 0.8 to 0.2 covers the last and the first fifth. Equal start and end cover it all.
+    pub fn pick_progress(&self, rng: &mut impl Rng) -> f32 {
+        let span = if self.arc_end > self.arc_start {
+            self.arc_end - self.arc_start
+        } else {
+            self.arc_end + 1.0 - self.arc_start
+        };
+
+        (self.arc_start + rng.random_range(0.0..=span)).fract()
+    }
+
+    pub fn pick_speed(&self, rng: &mut impl Rng) -> f32 {
+        rng.random_range(self.min_speed..=self.max_speed)
+    }
+}
+
+impl Default for WaveSpec {
+    // Same values as the original game, which spawned mobs endlessly.
+    fn default() -> Self {
+        Self {
+            mob_count: 20,
+            spawn_interval: 0.5,
+            min_speed: 10.0,
+            max_speed: 18.0,
+            arc_start: 0.0,
+            arc_end: 1.0,
+            delay: 1.0,
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// One authored wave of mobs.
+#[derive(GodotClass)]
+#[class(init, base=Resource)]
+pub struct Wave {
+    /// Number of mobs spawned in this wave.
+    #[export]
+    #[init(val = 10)]
+    mob_count: i64,
+
+    /// Seconds between two mob spawns.
+    #[export]
+    #[init(val = 0.5)]
+    spawn_interval: f64,
+
+    /// Minimum speed of a spawned mob, in meters per second.
+    #[export]
+    #[init(val = 10.0)]
+    min_speed: f32,
+
+    /// Maximum speed of a spawned mob, in meters per second.
+    #[export]
+    #[init(val = 18.0)]
+    max_speed: f32,
+
+    /// Where on the spawn path mobs start to appear, as fraction of the whole path.
+    #[export(range = (0.0, 1.0))]
+    arc_start: f32,
+
+    /// Where on the spawn path mobs stop appearing; may be less than `arc_start` to wrap around. Equal values use the whole path.
+    #[export(range = (0.0, 1.0))]
+    #[init(val = 1.0)]
+    arc_end: f32,
+
+    /// Seconds of intermission before this wave starts.
+    #[export]
+    #[init(val = 3.0)]
+    delay: f64,
+
+    base: Base<Resource>,
+}
+
+impl Wave {
+    fn to_spec(&self) -> WaveSpec {
+        WaveSpec {
+            mob_count: self.mob_count.max(1) as u32,
+            spawn_interval: self.spawn_interval.max(0.05),
+            min_speed: self.min_speed,
+            max_speed: self.max_speed.max(self.min_speed),
+            arc_start: self.arc_start.clamp(0.0, 1.0),
+            arc_end: self.arc_end.clamp(0.0, 1.0),
+            delay: self.delay.max(0.05),
+        }
+    }
+}
+
+// ----------------------------------------------------------------------------------------------------------------------------------------------
+
+/// The waves of a run, in order.
+///
+/// Once all waves are through, the last one repeats for as long as the player survives, a bit faster every time.
+#[derive(GodotClass)]
+#[class(init, base=Resource)]
+pub struct WaveList {
+    /// Slots left empty in the inspector are skipped.
+    #[export]
+    waves: Array<Option<Gd<Wave>>>,
+
+    /// Speed added to each repetition of the last wave, as a fraction of its original speed.
+    #[export]
+    #[init(val = 0.1)]
+    endless_speedup: f32,
+
+    base: Base<Resource>,
+}
+
+impl WaveList {
+    /// Wave number `index`, counting from 0. A list without any waves spawns like the original game.
+    pub fn wave(&self, index: usize) -> WaveSpec {
+        let waves: Vec<WaveSpec> = self
+            .waves
+            .iter_shared()
+            .flatten()
+            .map(|wave| wave.bind().to_spec())
+            .collect();
+
+        let Some(&last) = waves.last() else {
+            return WaveSpec::default();
+        };
+
+        if let Some(&spec) = waves.get(index) {
+            return spec;
+        }
+
+        let mut spec = last;
+        let factor = 1.0 + self.endless_speedup * (index - waves.len() + 1) as f32;
+        spec.min_speed *= factor;
+        spec.max_speed *= factor;
+        spec
+    }
+}